@@ -0,0 +1,164 @@
+// --- コマンドライン引数 ---
+
+use clap::Parser;
+use slide_generator::SvgFonts;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// スライドファイルからPDF (またはノートのページのPDF、スライドごとのSVG・PNG、1ファイルのHTML・PPTX) を生成する
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// 入力するスライドファイル (拡張子が .md なら Markdown として読む)
+    #[arg(default_value = "slides.txt")]
+    pub input: PathBuf,
+
+    /// 出力先 (ディレクトリが無ければ作成する)。PDF・ノート・HTML・PPTXならファイル、SVG・PNGならスライドごとのファイルを置くディレクトリ。
+    /// 省略時は outputs/output_multipage.pdf 、 outputs/notes.pdf 、 outputs/svg 、 outputs/png 、 outputs/slides.html または outputs/slides.pptx
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// 出力形式 (pdf 、 notes 、 svg 、 png 、 html または pptx)。notes はA4縦のページにスライドとスピーカーノートを載せたPDF
+    #[arg(long, default_value = "pdf")]
+    pub format: OutputFormat,
+
+    /// SVGのフォントを埋め込むか (embed)、フォントファイルを参照するか (link)
+    #[arg(long, default_value = "embed")]
+    pub svg_fonts: SvgFontsArg,
+
+    /// PNGの解像度 (1インチあたりの画素数)
+    #[arg(long, default_value_t = 96.0)]
+    pub dpi: f32,
+
+    /// PNGのとき、全スライドを並べた一覧の画像 (contact-sheet.png) も書き出す
+    #[arg(long)]
+    pub contact_sheet: bool,
+
+    /// 一覧の画像の横に並べる枚数
+    #[arg(long, default_value_t = 4)]
+    pub sheet_columns: usize,
+
+    /// テーマファイル (TOML)。省略時は組み込みの既定テーマ
+    #[arg(long)]
+    pub theme: Option<PathBuf>,
+
+    /// デッキのタイトル (PDFのタイトルとマスターの "{title}" に使う)。省略時は入力ファイル名
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// マスターの "{date}" に使う日付。省略時は今日の日付 (UTC で数えた YYYY-MM-DD)
+    #[arg(long)]
+    pub date: Option<String>,
+
+    /// 通常の文字に使うフォント (テーマの指定より優先)
+    #[arg(long)]
+    pub font_regular: Option<PathBuf>,
+
+    /// 太字に使うフォント (テーマの指定より優先)
+    #[arg(long)]
+    pub font_bold: Option<PathBuf>,
+
+    /// 斜体に使うフォント (テーマの指定より優先)
+    #[arg(long)]
+    pub font_italic: Option<PathBuf>,
+
+    /// コードブロックに使う等幅フォント (テーマの指定より優先)
+    #[arg(long)]
+    pub font_mono: Option<PathBuf>,
+
+    /// 基準フォントサイズ (pt)。1グリッドの大きさにもなる
+    #[arg(long, default_value_t = 24.0, value_parser = parse_base_size)]
+    pub base_size: f32,
+
+    /// ページのグリッド数 (横x縦)
+    #[arg(long, default_value = "32x18")]
+    pub grid: GridSize,
+
+    /// フォントやPDFの読み書きの警告と経過もすべて表示する。省略時はエラーと、隠した警告の件数だけを表示する
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+/// 出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Pdf,
+    Notes,
+    Svg,
+    Png,
+    Html,
+    Pptx,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pdf" => Ok(OutputFormat::Pdf),
+            "notes" => Ok(OutputFormat::Notes),
+            "svg" => Ok(OutputFormat::Svg),
+            "png" => Ok(OutputFormat::Png),
+            "html" => Ok(OutputFormat::Html),
+            "pptx" => Ok(OutputFormat::Pptx),
+            _ => Err(format!("expected pdf, notes, svg, png, html or pptx, got '{}'", s)),
+        }
+    }
+}
+
+/// SVGのフォントの渡し方
+#[derive(Debug, Clone, Copy)]
+pub struct SvgFontsArg(pub SvgFonts);
+
+impl FromStr for SvgFontsArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SvgFonts::from_name(s).map(SvgFontsArg).ok_or_else(|| format!("expected embed or link, got '{}'", s))
+    }
+}
+
+/// 基準フォントサイズは正の有限の値に限る (0 だとページの大きさが 0 になり、グリッド数の計算も 0 で割ることになる)
+fn parse_base_size(s: &str) -> Result<f32, String> {
+    s.trim().parse::<f32>().ok().filter(|v| v.is_finite() && *v > 0.0).ok_or_else(|| format!("invalid base size '{}'", s))
+}
+
+/// ページを何グリッド×何グリッドで区切るか
+#[derive(Debug, Clone, Copy)]
+pub struct GridSize {
+    pub cols: f32,
+    pub rows: f32,
+}
+
+impl FromStr for GridSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (cols, rows) = s.split_once('x').ok_or_else(|| format!("expected COLSxROWS (e.g. 32x18), got '{}'", s))?;
+        let parse = |v: &str| v.trim().parse::<f32>().ok().filter(|v| v.is_finite() && *v > 0.0).ok_or_else(|| format!("invalid grid size '{}'", s));
+        Ok(GridSize { cols: parse(cols)?, rows: parse(rows)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_sizes() {
+        let grid: GridSize = "32x18".parse().unwrap();
+        assert_eq!((grid.cols, grid.rows), (32.0, 18.0));
+        assert_eq!("0x18".parse::<GridSize>().unwrap_err(), "invalid grid size '0x18'");
+        assert_eq!("infx18".parse::<GridSize>().unwrap_err(), "invalid grid size 'infx18'");
+        assert_eq!("NaNx18".parse::<GridSize>().unwrap_err(), "invalid grid size 'NaNx18'");
+        assert_eq!("32".parse::<GridSize>().unwrap_err(), "expected COLSxROWS (e.g. 32x18), got '32'");
+    }
+
+    #[test]
+    fn base_sizes() {
+        assert_eq!(parse_base_size("24"), Ok(24.0));
+        assert!(parse_base_size("0").is_err());
+        assert!(parse_base_size("inf").is_err());
+        assert!(parse_base_size("NaN").is_err());
+    }
+}
@@ -0,0 +1,745 @@
+// --- ソースコードのブロック (シンタックスハイライト付き) ---
+//
+// コードは FontStyle::Mono のフォントで、折り返さずに1行ずつ描く。
+// 字句の色分けは行単位の簡単な字句解析で行い、複数行にまたがるコメントと文字列だけは次の行へ状態を持ち越す。
+
+use crate::error::SlideError;
+use crate::font::LoadedFont;
+use crate::page::{DrawOp, Frame};
+use crate::shape::shape_ops;
+use crate::text::{draw_text_block, span_width};
+use crate::{BlockLayout, Content, DrawConfig, FontStyle, HAlign, Shape, SlideColor, TextSpan};
+use std::collections::HashMap;
+
+/// タブを何文字の空白に置き換えるか
+const TAB_WIDTH: usize = 4;
+
+/// 色分けに使う言語
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeLanguage {
+    Rust,
+    Python,
+    Shell,
+    Json,
+    Plain, // 色分けしない
+}
+
+impl CodeLanguage {
+    /// "rust" "py" "sh" などの名前から言語を決める (知らない名前はNone)
+    pub fn from_name(name: &str) -> Option<CodeLanguage> {
+        match name.to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Some(CodeLanguage::Rust),
+            "python" | "py" => Some(CodeLanguage::Python),
+            "shell" | "sh" | "bash" | "zsh" | "console" => Some(CodeLanguage::Shell),
+            "json" => Some(CodeLanguage::Json),
+            "text" | "plain" | "txt" => Some(CodeLanguage::Plain),
+            _ => None,
+        }
+    }
+}
+
+/// 字句の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Text,
+    Keyword,
+    Type,
+    Function, // 関数呼び出し・マクロ・デコレーター
+    String,
+    Number,
+    Constant, // true / None / null やシェルのオプション
+    Variable, // シェルの $VAR や代入先
+    Property, // JSONのキー
+    Comment,
+}
+
+/// コードブロックの配色と大きさ
+#[derive(Debug, Clone)]
+pub struct CodeStyle {
+    pub background: Option<SlideColor>, // 枠の塗り
+    pub highlight: SlideColor,          // 強調する行の塗り
+    pub line_number: SlideColor,
+    pub size_ratio: f32,
+    pub line_spacing_ratio: f32,
+    pub padding: f32, // 枠の内側の余白 (グリッド単位)
+    pub text: SlideColor,
+    pub keyword: SlideColor,
+    pub type_name: SlideColor,
+    pub function: SlideColor,
+    pub string: SlideColor,
+    pub number: SlideColor,
+    pub constant: SlideColor,
+    pub variable: SlideColor,
+    pub property: SlideColor,
+    pub comment: SlideColor,
+}
+
+impl Default for CodeStyle {
+    fn default() -> Self {
+        let rgb = |hex: u32| {
+            let channel = |shift: u32| ((hex >> shift) & 0xff) as f32 / 255.0;
+            SlideColor::Custom(channel(16), channel(8), channel(0))
+        };
+        CodeStyle {
+            background: Some(rgb(0xf4f4f7)),
+            highlight: rgb(0xfff1b8),
+            line_number: rgb(0x9a9aa6),
+            size_ratio: 0.8,
+            line_spacing_ratio: 1.3,
+            padding: 0.5,
+            text: rgb(0x24292f),
+            keyword: rgb(0xa626a4),
+            type_name: rgb(0xc18401),
+            function: rgb(0x4078f2),
+            string: rgb(0x50a14f),
+            number: rgb(0x986801),
+            constant: rgb(0x0184bc),
+            variable: rgb(0xe45649),
+            property: rgb(0xe45649),
+            comment: rgb(0xa0a1a7),
+        }
+    }
+}
+
+impl CodeStyle {
+    fn color(&self, kind: TokenKind) -> SlideColor {
+        match kind {
+            TokenKind::Text => self.text,
+            TokenKind::Keyword => self.keyword,
+            TokenKind::Type => self.type_name,
+            TokenKind::Function => self.function,
+            TokenKind::String => self.string,
+            TokenKind::Number => self.number,
+            TokenKind::Constant => self.constant,
+            TokenKind::Variable => self.variable,
+            TokenKind::Property => self.property,
+            TokenKind::Comment => self.comment,
+        }
+    }
+}
+
+/// コードブロック1つ分
+#[derive(Debug, Clone)]
+pub struct CodeBlock {
+    pub source: String,
+    pub language: CodeLanguage,
+    pub line_numbers: bool,
+    pub highlight: Vec<(usize, usize)>, // 強調する行の範囲 (1始まり、両端を含む)
+    pub style: CodeStyle,
+}
+
+impl CodeBlock {
+    pub fn new(source: impl Into<String>, language: CodeLanguage) -> Self {
+        CodeBlock { source: source.into(), language, line_numbers: false, highlight: Vec::new(), style: CodeStyle::default() }
+    }
+
+    pub fn with_line_numbers(mut self, line_numbers: bool) -> Self {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    /// `first` 行目から `last` 行目までを強調する
+    pub fn with_highlight(mut self, first: usize, last: usize) -> Self {
+        self.highlight.push((first, last));
+        self
+    }
+
+    pub fn with_style(mut self, style: CodeStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// 色分けした字句を行ごとに返す
+    pub fn tokens(&self) -> Vec<Vec<(TokenKind, String)>> {
+        let mut state = LexState::Normal;
+        self.lines().map(|line| lex_line(&line.chars().collect::<Vec<char>>(), self.language, &mut state)).collect()
+    }
+
+    /// タブを空白に展開した各行 (末尾の改行は行に数えない)
+    fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        self.source.trim_end_matches('\n').split('\n').map(|line| line.trim_end_matches('\r').replace('\t', &" ".repeat(TAB_WIDTH)))
+    }
+}
+
+/// コードブロックを `layout` の位置 (枠の左上) から描画し、次に使える行の位置を返す。
+/// 枠の幅は `layout.width` (無ければ一番長い行に合わせる)。はみ出した部分は枠で切り取る
+pub(crate) fn draw_code(
+    ops: &mut Vec<DrawOp>,
+    fonts: &HashMap<FontStyle, LoadedFont>,
+    config: &DrawConfig,
+    block: &CodeBlock,
+    layout: &BlockLayout,
+) -> Result<f32, SlideError> {
+    let style = &block.style;
+    let span = |text: String, color: SlideColor| TextSpan { text, style: FontStyle::Mono, size_ratio: style.size_ratio, color, link: None };
+
+    // --- 1. 字句を色付きのSpanにする ---
+    let lines: Vec<Vec<TextSpan>> = block
+        .tokens()
+        .into_iter()
+        .map(|tokens| tokens.into_iter().map(|(kind, text)| span(text, style.color(kind))).collect())
+        .collect();
+    let line_height = style.size_ratio * style.line_spacing_ratio;
+
+    // --- 2. 行番号の幅と枠の大きさを決める ---
+    let gutter = if block.line_numbers {
+        let widest = span(lines.len().to_string(), style.line_number);
+        span_width(fonts, &widest)? + style.size_ratio
+    } else {
+        0.0
+    };
+    let box_width = match layout.width {
+        Some(width) => width,
+        None => {
+            let mut widest = 0.0f32;
+            for line in &lines {
+                widest = widest.max(line.iter().map(|span| span_width(fonts, span)).sum::<Result<f32, _>>()?);
+            }
+            widest + gutter + style.padding * 2.0
+        }
+    };
+    let box_height = lines.len() as f32 * line_height + style.padding * 2.0;
+    let (left, top) = (layout.start_col, layout.start_row);
+    let text_top = top + style.padding;
+
+    // --- 3. 枠と強調行の塗り ---
+    if let Some(background) = style.background {
+        ops.extend(shape_ops(&Shape::rect(left, top, box_width, box_height).with_fill(background).without_stroke(), config));
+    }
+    for line_index in 0..lines.len() {
+        let line_no = line_index + 1;
+        if block.highlight.iter().any(|&(first, last)| (first..=last).contains(&line_no)) {
+            let row = text_top + line_index as f32 * line_height;
+            ops.extend(shape_ops(&Shape::rect(left, row, box_width, line_height).with_fill(style.highlight).without_stroke(), config));
+        }
+    }
+
+    // --- 4. 行番号とコード (枠の外にはみ出した部分は描かない) ---
+    let grid = config.base_font_size.0;
+    ops.push(DrawOp::PushClip(Frame {
+        x: left * grid,
+        y_top: config.page_height_pt.0 - top * grid,
+        width: box_width * grid,
+        height: box_height * grid,
+    }));
+    let code_layout = |start_col: f32, width: Option<f32>, h_align: HAlign| BlockLayout {
+        start_col,
+        start_row: text_top,
+        // draw_text_block は1行の高さを最低でも1グリッドとして行送りするので、その分を割り戻す
+        line_spacing_ratio: line_height / style.size_ratio.max(1.0),
+        width,
+        h_align,
+        ..*layout
+    };
+    if block.line_numbers {
+        let mut numbers: Vec<Content> = Vec::new();
+        for line_no in 1..=lines.len() {
+            if line_no > 1 {
+                numbers.push(Content::Newline);
+            }
+            numbers.push(Content::Span(span(line_no.to_string(), style.line_number)));
+        }
+        // 行番号は右揃えにし、コードとの間に1文字分空ける
+        let number_layout = code_layout(left + style.padding, Some(gutter - style.size_ratio), HAlign::Right);
+        draw_text_block(ops, fonts, config, &numbers, &number_layout)?;
+    }
+    let mut contents: Vec<Content> = Vec::new();
+    for (i, line) in lines.into_iter().enumerate() {
+        if i > 0 {
+            contents.push(Content::Newline);
+        }
+        contents.extend(line.into_iter().map(Content::Span));
+    }
+    draw_text_block(ops, fonts, config, &contents, &code_layout(left + style.padding + gutter, None, HAlign::Left))?;
+    ops.push(DrawOp::PopClip);
+
+    // 枠の下には、行間と同じだけの空きを取る
+    Ok(top + box_height + (layout.line_spacing_ratio - 1.0).max(0.0))
+}
+
+/// 閉じの文字列で終わる領域 (コメント・文字列)
+#[derive(Debug, Clone, PartialEq)]
+struct Region {
+    close: String, // Rustの生文字列 (r##"…"##) は "#" の数で閉じが変わる
+    kind: TokenKind,
+    escapes: bool, // バックスラッシュの次の文字を閉じとみなさない
+}
+
+/// 行をまたいで続いているコメント・文字列
+#[derive(Debug, Clone, PartialEq)]
+enum LexState {
+    Normal,
+    Open(Region),
+}
+
+/// 同じ種類の字句が続いたら1つにまとめながら積む
+#[derive(Default)]
+struct Tokens(Vec<(TokenKind, String)>);
+
+impl Tokens {
+    fn push(&mut self, kind: TokenKind, chars: &[char]) {
+        if chars.is_empty() {
+            return;
+        }
+        // 空白は直前の字句にまとめる (Spanの数を減らすため)
+        let kind = if chars.iter().all(|c| c.is_whitespace()) { self.0.last().map_or(TokenKind::Text, |(kind, _)| *kind) } else { kind };
+        match self.0.last_mut() {
+            Some((last, text)) if *last == kind => text.extend(chars),
+            _ => self.0.push((kind, chars.iter().collect())),
+        }
+    }
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "fn", "for", "if", "impl", "in",
+    "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "type",
+    "unsafe", "use", "where", "while",
+];
+const RUST_TYPES: &[&str] = &[
+    "bool", "char", "str", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize", "f32", "f64",
+];
+const PYTHON_KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "async", "await", "break", "case", "class", "continue", "def", "del", "elif", "else", "except",
+    "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "match", "nonlocal", "not", "or", "pass", "raise",
+    "return", "try", "while", "with", "yield",
+];
+const SHELL_KEYWORDS: &[&str] = &[
+    "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in", "local", "return", "select",
+    "then", "until", "while",
+];
+
+/// 1行分を字句に分ける。`state` は前の行から続くコメント・文字列で、この行で閉じなければ次の行へ持ち越す
+fn lex_line(chars: &[char], language: CodeLanguage, state: &mut LexState) -> Vec<(TokenKind, String)> {
+    let mut tokens = Tokens::default();
+    if language == CodeLanguage::Plain {
+        tokens.push(TokenKind::Text, chars);
+        return tokens.0;
+    }
+
+    let mut i = 0;
+    if let LexState::Open(region) = state.clone() {
+        match find_close(chars, 0, &region.close, region.escapes) {
+            Some(end) => {
+                tokens.push(region.kind, &chars[..end]);
+                i = end;
+                *state = LexState::Normal;
+            }
+            None => {
+                tokens.push(region.kind, chars);
+                return tokens.0;
+            }
+        }
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+        let rest = &chars[i..];
+        let at_word_start = i == 0 || chars[i - 1].is_whitespace();
+
+        // --- コメント ---
+        let line_comment = match language {
+            CodeLanguage::Rust => starts_with(rest, "//"),
+            CodeLanguage::Python => c == '#',
+            CodeLanguage::Shell => c == '#' && at_word_start,
+            _ => false,
+        };
+        if line_comment {
+            tokens.push(TokenKind::Comment, rest);
+            break;
+        }
+        if language == CodeLanguage::Rust && starts_with(rest, "/*") {
+            let region = Region { close: "*/".to_string(), kind: TokenKind::Comment, escapes: false };
+            i = open_region(chars, i, 2, region, &mut tokens, state);
+            continue;
+        }
+
+        // --- 文字列 ---
+        if let Some((open_len, close, escapes, multiline)) = string_start(rest, language) {
+            let kind = if language == CodeLanguage::Json && is_json_key(chars, i + open_len) { TokenKind::Property } else { TokenKind::String };
+            if multiline {
+                i = open_region(chars, i, open_len, Region { close, kind, escapes }, &mut tokens, state);
+            } else {
+                // 1行で閉じない文字列は行末までとする
+                let end = find_close(chars, i + open_len, &close, escapes).unwrap_or(chars.len());
+                tokens.push(kind, &chars[i..end]);
+                i = end;
+            }
+            continue;
+        }
+
+        // --- 数値 ---
+        if c.is_ascii_digit() {
+            let mut end = i + 1;
+            while end < chars.len()
+                && (chars[end].is_ascii_alphanumeric() || chars[end] == '_' || (chars[end] == '.' && chars.get(end + 1).is_some_and(char::is_ascii_digit)))
+            {
+                end += 1;
+            }
+            tokens.push(TokenKind::Number, &chars[i..end]);
+            i = end;
+            continue;
+        }
+
+        // --- 言語ごとの記号 ---
+        match language {
+            CodeLanguage::Shell if c == '$' => {
+                let end = shell_variable_end(chars, i);
+                tokens.push(TokenKind::Variable, &chars[i..end]);
+                i = end;
+                continue;
+            }
+            CodeLanguage::Shell if c == '-' && at_word_start && rest.get(1).is_some_and(|c| c.is_ascii_alphabetic() || *c == '-') => {
+                let end = (i..chars.len()).find(|&j| chars[j].is_whitespace() || chars[j] == '=').unwrap_or(chars.len());
+                tokens.push(TokenKind::Constant, &chars[i..end]);
+                i = end;
+                continue;
+            }
+            CodeLanguage::Python if c == '@' && rest.get(1).is_some_and(|c| is_ident_start(*c)) => {
+                let end = ident_end(chars, i + 1);
+                tokens.push(TokenKind::Function, &chars[i..end]);
+                i = end;
+                continue;
+            }
+            // ライフタイム ('a) は型と同じ色にする
+            CodeLanguage::Rust if c == '\'' && rest.get(1).is_some_and(|c| is_ident_start(*c)) => {
+                let end = ident_end(chars, i + 1);
+                tokens.push(TokenKind::Type, &chars[i..end]);
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+
+        // --- 識別子 ---
+        if is_ident_start(c) {
+            let end = ident_end(chars, i);
+            let word: String = chars[i..end].iter().collect();
+            let next = chars[end..].iter().find(|c| !c.is_whitespace()).copied();
+            tokens.push(classify_word(&word, language, chars.get(end).copied(), next), &chars[i..end]);
+            i = end;
+            continue;
+        }
+
+        tokens.push(TokenKind::Text, &chars[i..i + 1]);
+        i += 1;
+    }
+    tokens.0
+}
+
+/// 識別子の種類を決める。`following` は直後の文字、`next` は空白を飛ばした次の文字
+fn classify_word(word: &str, language: CodeLanguage, following: Option<char>, next: Option<char>) -> TokenKind {
+    let capitalized = word.chars().next().is_some_and(|c| c.is_ascii_uppercase());
+    match language {
+        CodeLanguage::Rust => match word {
+            "true" | "false" => TokenKind::Constant,
+            _ if RUST_KEYWORDS.contains(&word) => TokenKind::Keyword,
+            _ if RUST_TYPES.contains(&word) => TokenKind::Type,
+            _ if following == Some('!') || next == Some('(') => TokenKind::Function,
+            _ if capitalized => TokenKind::Type,
+            _ => TokenKind::Text,
+        },
+        CodeLanguage::Python => match word {
+            "True" | "False" | "None" => TokenKind::Constant,
+            _ if PYTHON_KEYWORDS.contains(&word) => TokenKind::Keyword,
+            _ if next == Some('(') => TokenKind::Function,
+            _ if capitalized => TokenKind::Type,
+            _ => TokenKind::Text,
+        },
+        CodeLanguage::Shell => match word {
+            _ if SHELL_KEYWORDS.contains(&word) => TokenKind::Keyword,
+            _ if following == Some('=') => TokenKind::Variable,
+            _ => TokenKind::Text,
+        },
+        CodeLanguage::Json => match word {
+            "true" | "false" | "null" => TokenKind::Constant,
+            _ => TokenKind::Text,
+        },
+        CodeLanguage::Plain => TokenKind::Text,
+    }
+}
+
+/// 文字列の始まりなら (開きの長さ, 閉じ, エスケープの有無, 行をまたげるか) を返す
+fn string_start(rest: &[char], language: CodeLanguage) -> Option<(usize, String, bool, bool)> {
+    let close = |close: &str| close.to_string();
+    match language {
+        CodeLanguage::Rust => {
+            // 生文字列 (r"…" r#"…"# br##"…"## など) は、開きと同じ数の "#" が続く '"' で閉じる
+            let raw = usize::from(rest[0] == 'b');
+            if rest.get(raw) == Some(&'r') {
+                let hashes = rest[raw + 1..].iter().take_while(|c| **c == '#').count();
+                if rest.get(raw + 1 + hashes) == Some(&'"') {
+                    return Some((raw + hashes + 2, format!("\"{}", "#".repeat(hashes)), false, true));
+                }
+            }
+            if starts_with(rest, "b\"") {
+                return Some((2, close("\""), true, true));
+            }
+            if rest[0] == '"' {
+                return Some((1, close("\""), true, true));
+            }
+            // 文字リテラル ('x' '\n')。ライフタイムとは閉じの ' の有無で区別する
+            if rest[0] == '\'' && (rest.get(1) == Some(&'\\') || rest.get(2) == Some(&'\'')) {
+                return Some((1, close("'"), true, false));
+            }
+            None
+        }
+        CodeLanguage::Python => {
+            // 接頭辞 (f"" r"" b"" rb"" など) を読み飛ばす
+            let prefix = rest.iter().take(2).take_while(|c| "rRbBfFuU".contains(**c)).count();
+            let quoted = &rest[prefix..];
+            if starts_with(quoted, "\"\"\"") {
+                return Some((prefix + 3, close("\"\"\""), true, true));
+            }
+            if starts_with(quoted, "'''") {
+                return Some((prefix + 3, close("'''"), true, true));
+            }
+            match quoted.first() {
+                Some('"') => Some((prefix + 1, close("\""), true, false)),
+                Some('\'') => Some((prefix + 1, close("'"), true, false)),
+                _ => None,
+            }
+        }
+        CodeLanguage::Shell => match rest[0] {
+            '"' => Some((1, close("\""), true, true)),
+            '\'' => Some((1, close("'"), false, true)),
+            _ => None,
+        },
+        CodeLanguage::Json => (rest[0] == '"').then(|| (1, close("\""), true, false)),
+        CodeLanguage::Plain => None,
+    }
+}
+
+/// chars[start..] から始まる領域 (開きの長さは `open_len`) を字句にし、次の位置を返す。
+/// 行内で閉じなければ `state` に持ち越す
+fn open_region(chars: &[char], start: usize, open_len: usize, region: Region, tokens: &mut Tokens, state: &mut LexState) -> usize {
+    match find_close(chars, start + open_len, &region.close, region.escapes) {
+        Some(end) => {
+            tokens.push(region.kind, &chars[start..end]);
+            end
+        }
+        None => {
+            tokens.push(region.kind, &chars[start..]);
+            *state = LexState::Open(region);
+            chars.len()
+        }
+    }
+}
+
+/// `from` 以降で `close` を探し、その直後の位置を返す。`escapes` ならバックスラッシュの次の文字は読み飛ばす
+fn find_close(chars: &[char], from: usize, close: &str, escapes: bool) -> Option<usize> {
+    let close_len = close.chars().count();
+    let mut i = from;
+    while i < chars.len() {
+        if escapes && chars[i] == '\\' {
+            i += 2;
+            continue;
+        }
+        if starts_with(&chars[i..], close) {
+            return Some(i + close_len);
+        }
+        i += 1;
+    }
+    None
+}
+
+fn starts_with(chars: &[char], prefix: &str) -> bool {
+    prefix.chars().enumerate().all(|(i, p)| chars.get(i) == Some(&p))
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn ident_end(chars: &[char], start: usize) -> usize {
+    (start..chars.len()).find(|&i| !(chars[i].is_alphanumeric() || chars[i] == '_')).unwrap_or(chars.len())
+}
+
+/// JSONの文字列が、閉じた後に ':' が続くキーかどうか
+fn is_json_key(chars: &[char], content_start: usize) -> bool {
+    find_close(chars, content_start, "\"", true)
+        .is_some_and(|end| chars[end..].iter().find(|c| !c.is_whitespace()) == Some(&':'))
+}
+
+/// "$NAME" "${...}" "$(" "$1" "$@" の終わりの位置
+fn shell_variable_end(chars: &[char], start: usize) -> usize {
+    match chars.get(start + 1) {
+        Some('{') => find_close(chars, start + 2, "}", false).unwrap_or(chars.len()),
+        Some('(') => start + 2,
+        Some(c) if is_ident_start(*c) => ident_end(chars, start + 1),
+        Some(c) if c.is_ascii_digit() || "@*#?$!-".contains(*c) => start + 2,
+        _ => start + 1,
+    }
+}
+
+/// コードフェンスの情報文字列 ("```" の後ろの "rust numbers highlight=2-3,5") から、中身が空のコードブロックを作る。
+/// 言語名は省略でき、知らない言語名は色分けしない。解釈できないオプションはその語を返す
+pub(crate) fn parse_fence_info(info: &str) -> Result<CodeBlock, String> {
+    let words: Vec<&str> = info.split_whitespace().collect();
+    let (language, options) = match words.split_first() {
+        Some((name, options)) if *name != "numbers" && !name.contains('=') => {
+            (CodeLanguage::from_name(name).unwrap_or(CodeLanguage::Plain), options)
+        }
+        _ => (CodeLanguage::Plain, &words[..]),
+    };
+    let (line_numbers, highlight) = parse_fence_options(options)?;
+    let mut code = CodeBlock::new(String::new(), language).with_line_numbers(line_numbers);
+    code.highlight = highlight;
+    Ok(code)
+}
+
+/// 情報文字列の言語名以外の部分を解釈する。戻り値は (行番号を付けるか, 強調する行の範囲)
+fn parse_fence_options(words: &[&str]) -> Result<(bool, Vec<(usize, usize)>), String> {
+    let mut line_numbers = false;
+    let mut highlight = Vec::new();
+    for word in words {
+        if *word == "numbers" {
+            line_numbers = true;
+        } else if let Some(ranges) = word.strip_prefix("highlight=") {
+            for range in ranges.split(',') {
+                let (first, last) = range.split_once('-').unwrap_or((range, range));
+                match (first.trim().parse::<usize>(), last.trim().parse::<usize>()) {
+                    (Ok(first), Ok(last)) if first >= 1 && first <= last => highlight.push((first, last)),
+                    _ => return Err(word.to_string()),
+                }
+            }
+        } else {
+            return Err(word.to_string());
+        }
+    }
+    Ok((line_numbers, highlight))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(source: &str, language: CodeLanguage) -> Vec<Vec<(TokenKind, String)>> {
+        CodeBlock::new(source, language).tokens()
+    }
+
+    /// 1行目の字句を (種類, 前後の空白を除いた字句) の列にする
+    fn first_line(source: &str, language: CodeLanguage) -> Vec<(TokenKind, String)> {
+        lex(source, language)[0].iter().map(|(kind, text)| (*kind, text.trim().to_string())).collect()
+    }
+
+    fn kinds_of(tokens: &[(TokenKind, String)], kind: TokenKind) -> Vec<&str> {
+        tokens.iter().filter(|(k, _)| *k == kind).map(|(_, text)| text.as_str()).collect()
+    }
+
+    fn tokens(list: &[(TokenKind, &str)]) -> Vec<(TokenKind, String)> {
+        list.iter().map(|(kind, text)| (*kind, text.to_string())).collect()
+    }
+
+    #[test]
+    fn rust_words() {
+        let line = first_line("pub fn main(x: u32) -> Option<Vec<u8>> { println!(\"{}\", x); }", CodeLanguage::Rust);
+        assert_eq!(kinds_of(&line, TokenKind::Keyword), ["pub fn"]);
+        assert_eq!(kinds_of(&line, TokenKind::Function), ["main", "println"]);
+        assert_eq!(kinds_of(&line, TokenKind::Type), ["u32", "Option", "Vec", "u8"]);
+        assert_eq!(kinds_of(&line, TokenKind::String), ["\"{}\""]);
+    }
+
+    #[test]
+    fn rust_literals_and_lifetimes() {
+        let line = first_line("let c = '\\n'; fn f<'a>(s: &'a str) -> bool { true } [1_000, 2.5]", CodeLanguage::Rust);
+        assert_eq!(kinds_of(&line, TokenKind::String), ["'\\n'"]);
+        assert_eq!(kinds_of(&line, TokenKind::Type), ["'a", "'a str", "bool"]);
+        assert_eq!(kinds_of(&line, TokenKind::Constant), ["true"]);
+        assert_eq!(kinds_of(&line, TokenKind::Number), ["1_000", "2.5"]);
+    }
+
+    #[test]
+    fn rust_comments() {
+        assert_eq!(
+            first_line("x /* a */ y // rest", CodeLanguage::Rust),
+            tokens(&[(TokenKind::Text, "x"), (TokenKind::Comment, "/* a */"), (TokenKind::Text, "y"), (TokenKind::Comment, "// rest")])
+        );
+        // ブロックコメントは次の行へ続く
+        let lines = lex("a /* one\ntwo */ b", CodeLanguage::Rust);
+        assert_eq!(lines[0], tokens(&[(TokenKind::Text, "a "), (TokenKind::Comment, "/* one")]));
+        assert_eq!(lines[1], tokens(&[(TokenKind::Comment, "two */ "), (TokenKind::Text, "b")]));
+    }
+
+    #[test]
+    fn rust_raw_strings_close_with_the_same_number_of_hashes() {
+        let line = first_line(r####"let s = r##"a "# b"## + x;"####, CodeLanguage::Rust);
+        assert_eq!(kinds_of(&line, TokenKind::String), [r###"r##"a "# b"##"###]);
+        assert_eq!(kinds_of(&line, TokenKind::Text), ["s =", "+ x;"]);
+
+        assert_eq!(kinds_of(&first_line(r#"br"\" x"#, CodeLanguage::Rust), TokenKind::String), [r#"br"\""#]);
+        // 生の識別子 (r#type) は文字列ではない
+        assert!(kinds_of(&first_line("r#type", CodeLanguage::Rust), TokenKind::String).is_empty());
+
+        let lines = lex("r#\"one\n\"two\"#;", CodeLanguage::Rust);
+        assert_eq!(lines[1], tokens(&[(TokenKind::String, "\"two\"#"), (TokenKind::Text, ";")]));
+    }
+
+    #[test]
+    fn python() {
+        let line = first_line("def f(x=None): return g(x) # done", CodeLanguage::Python);
+        assert_eq!(kinds_of(&line, TokenKind::Keyword), ["def", "return"]);
+        assert_eq!(kinds_of(&line, TokenKind::Function), ["f", "g"]);
+        assert_eq!(kinds_of(&line, TokenKind::Constant), ["None"]);
+        assert_eq!(kinds_of(&line, TokenKind::Comment), ["# done"]);
+
+        assert_eq!(first_line("@app.route", CodeLanguage::Python)[0], (TokenKind::Function, "@app".to_string()));
+        assert_eq!(kinds_of(&first_line("s = f'{x}' + rb\"y\"", CodeLanguage::Python), TokenKind::String), ["f'{x}'", "rb\"y\""]);
+
+        let lines = lex("\"\"\"doc\nstill doc\n\"\"\" + x", CodeLanguage::Python);
+        assert_eq!(lines[1], tokens(&[(TokenKind::String, "still doc")]));
+        assert_eq!(lines[2], tokens(&[(TokenKind::String, "\"\"\" "), (TokenKind::Text, "+ x")]));
+    }
+
+    #[test]
+    fn shell() {
+        let line = first_line("if [ -n \"$HOME\" ]; then export PATH=${X}:$1 --force a#b # note", CodeLanguage::Shell);
+        assert_eq!(kinds_of(&line, TokenKind::Keyword), ["if", "then export"]);
+        assert_eq!(kinds_of(&line, TokenKind::Constant), ["-n", "--force"]);
+        assert_eq!(kinds_of(&line, TokenKind::String), ["\"$HOME\""]);
+        assert_eq!(kinds_of(&line, TokenKind::Variable), ["PATH", "${X}", "$1"]);
+        // 語の途中の "#" はコメントではない
+        assert_eq!(kinds_of(&line, TokenKind::Comment), ["# note"]);
+    }
+
+    #[test]
+    fn json_keys_and_values() {
+        let line = first_line(r#"{"name": "x", "n": -1.5e3, "ok": true, "v": null}"#, CodeLanguage::Json);
+        assert_eq!(kinds_of(&line, TokenKind::Property), [r#""name""#, r#""n""#, r#""ok""#, r#""v""#]);
+        assert_eq!(kinds_of(&line, TokenKind::String), [r#""x""#]);
+        assert_eq!(kinds_of(&line, TokenKind::Number), ["1.5e3"]);
+        assert_eq!(kinds_of(&line, TokenKind::Constant), ["true", "null"]);
+    }
+
+    #[test]
+    fn plain_and_tabs() {
+        assert_eq!(lex("fn x\t// y\n", CodeLanguage::Plain), [tokens(&[(TokenKind::Text, "fn x    // y")])]);
+    }
+
+    #[test]
+    fn fence_info() {
+        let code = parse_fence_info("rust numbers highlight=2-3,5").unwrap();
+        assert_eq!(code.language, CodeLanguage::Rust);
+        assert!(code.line_numbers);
+        assert_eq!(code.highlight, [(2, 3), (5, 5)]);
+
+        let code = parse_fence_info("highlight=1").unwrap();
+        assert_eq!((code.language, code.line_numbers, code.highlight), (CodeLanguage::Plain, false, vec![(1, 1)]));
+        assert_eq!(parse_fence_info("numbers").unwrap().language, CodeLanguage::Plain);
+        assert_eq!(parse_fence_info("  ").unwrap().language, CodeLanguage::Plain);
+        // 知らない言語名は色分けしないだけで、エラーにはしない
+        assert_eq!(parse_fence_info("cobol").unwrap().language, CodeLanguage::Plain);
+    }
+
+    #[test]
+    fn fence_info_errors_return_the_word() {
+        for (info, word) in [
+            ("rust highlight=3-2", "highlight=3-2"),
+            ("rust highlight=0", "highlight=0"),
+            ("rust highlight=1,x", "highlight=1,x"),
+            ("rust highlight=", "highlight="),
+            ("rust numbers wrap", "wrap"),
+        ] {
+            assert_eq!(parse_fence_info(info).err().as_deref(), Some(word), "{}", info);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use printpdf::{Color, Rgb};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy)]
+pub enum NamedColor { Black, White, Red, Green, Blue }
+
+#[derive(Debug, Clone, Copy)]
+pub enum SlideColor {
+    Named(NamedColor),
+    Custom(f32, f32, f32), // f32からf32に変更し、精度を統一
+}
+
+impl SlideColor {
+    /// 色名、または "#rrggbb" 形式の文字列を色に変換する。
+    /// `palette` (テーマで定義した色名) は組み込みの色名より優先される。
+    pub fn parse(value: &str, palette: &HashMap<String, SlideColor>) -> Option<SlideColor> {
+        let name = value.to_ascii_lowercase();
+        if let Some(color) = palette.get(&name) {
+            return Some(*color);
+        }
+        let named = match name.as_str() {
+            "black" => Some(NamedColor::Black),
+            "white" => Some(NamedColor::White),
+            "red" => Some(NamedColor::Red),
+            "green" => Some(NamedColor::Green),
+            "blue" => Some(NamedColor::Blue),
+            _ => None,
+        };
+        if let Some(named) = named {
+            return Some(SlideColor::Named(named));
+        }
+
+        let hex = value.strip_prefix('#')?;
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |range: std::ops::Range<usize>| u8::from_str_radix(&hex[range], 16).ok().map(|v| v as f32 / 255.0);
+        Some(SlideColor::Custom(channel(0..2)?, channel(2..4)?, channel(4..6)?))
+    }
+
+    /// RGBの各成分 (0.0〜1.0)
+    pub(crate) fn rgb(self) -> (f32, f32, f32) {
+        match self {
+            SlideColor::Named(named) => match named {
+                NamedColor::Black => (0.0, 0.0, 0.0),
+                NamedColor::White => (1.0, 1.0, 1.0),
+                NamedColor::Red   => (0.8, 0.0, 0.0),
+                NamedColor::Green => (0.0, 0.8, 0.0),
+                NamedColor::Blue  => (0.0, 0.0, 0.8),
+            },
+            SlideColor::Custom(r, g, b) => (r, g, b),
+        }
+    }
+
+    pub(crate) fn into_pdf_color(self) -> Color {
+        let (r, g, b) = self.rgb();
+        Color::Rgb(Rgb::new(r, g, b, None))
+    }
+
+    /// "#rrggbb" 形式 (SVGやHTMLで使う)
+    pub(crate) fn to_hex(self) -> String {
+        let (r, g, b) = self.rgb();
+        let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+    }
+}
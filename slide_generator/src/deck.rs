@@ -0,0 +1,979 @@
+// --- デッキ (PDF全体) とスライドのビルダー ---
+
+use crate::error::SlideError;
+use crate::code::{draw_code, parse_fence_info};
+use crate::font::{load_font, LoadedFont};
+use crate::html::HtmlRenderer;
+use crate::image::ImageLayout;
+use crate::layout::SlideLayout;
+use crate::list::{draw_list, parse_list_line};
+use crate::markdown::MarkdownSlide;
+use crate::outline::{finish_pdf, OutlineEntry};
+use crate::page::{DrawOp, Frame, Page, Renderer, Stroke};
+use crate::pdf::PdfRenderer;
+use crate::pptx::{text_box, PptxItem, PptxWriter};
+use crate::raster::{contact_sheet, encode_png, PngRenderer};
+use crate::svg::{SvgFonts, SvgRenderer};
+use crate::table::draw_table;
+use crate::shape::{fill_rect, shape_ops, stroke_rect};
+use crate::parser::{
+    parse_background, parse_image, parse_layout, parse_shape, parse_table, split_table_row, Directive, ParseError, SlideSource,
+    SourceLine, CODE_FENCE,
+};
+use crate::text::draw_text_block;
+use crate::{
+    BlockLayout, CodeBlock, Content, DrawConfig, FontStyle, ImageFit, LinkTarget, ListItem,
+    Shape, SlideColor, Table,
+};
+use printpdf::{PdfDocument, PdfWarnMsg, Pt};
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// ページの背景。色で塗りつぶしたうえに画像を重ねられる
+#[derive(Debug, Clone, Default)]
+pub struct Background {
+    pub color: Option<SlideColor>,
+    pub image: Option<BackgroundImage>,
+}
+
+/// ページ全体に敷く背景画像
+#[derive(Debug, Clone)]
+pub struct BackgroundImage {
+    pub path: PathBuf,
+    pub fit: ImageFit,
+}
+
+/// スライド上に置かれたテキストブロック
+#[derive(Debug, Clone)]
+struct TextBlock {
+    contents: Vec<Content>,
+    layout: BlockLayout,
+}
+
+/// 本文のように、上から順に積み重ねて描くブロック
+#[derive(Debug, Clone)]
+pub enum FlowBlock {
+    List(Vec<ListItem>),
+    Table(Table),
+    Code(CodeBlock),
+}
+
+/// スライド上に置かれた、積み重ねるブロックの並び
+#[derive(Debug, Clone)]
+struct Flow {
+    blocks: Vec<FlowBlock>,
+    layout: BlockLayout,
+}
+
+/// スライド上に置かれた画像
+#[derive(Debug, Clone)]
+struct ImageBlock {
+    path: PathBuf,
+    layout: ImageLayout,
+}
+
+/// スライド上の要素。後から追加したものほど手前に描かれる
+#[derive(Debug, Clone)]
+enum Element {
+    Text(TextBlock),
+    Flow(Flow),
+    Image(ImageBlock),
+    Shape(Shape),
+}
+
+/// スライドに使うマスター
+#[derive(Debug, Clone, Default)]
+enum MasterChoice {
+    #[default]
+    Default, // "default" という名前のマスター (無ければ何も描かない)
+    Named(String),
+    None,
+}
+
+/// スライド1枚分。要素は追加した順に描画される
+#[derive(Debug, Clone, Default)]
+pub struct Slide {
+    elements: Vec<Element>,
+    background: Option<Background>, // Noneならデッキ共通の背景 (DrawConfig::background) を使う
+    master: MasterChoice,
+    bookmark: Option<String>, // PDFのしおりに載せる見出し
+    section: bool,            // 章の扉 (後に続くスライドのしおりをこのスライドの下にまとめる)
+    notes: Vec<Content>,      // スピーカーノート (スライドには描かず、ノートのページに載せる)
+}
+
+impl Slide {
+    /// このスライドを `title` という見出しでPDFのしおりに載せる
+    pub fn set_bookmark(&mut self, title: impl Into<String>) -> &mut Self {
+        self.bookmark = Some(title.into());
+        self
+    }
+
+    /// このスライドを章の扉にする。次の章の扉までのスライドは、しおりでこのスライドの下に入る
+    pub fn start_section(&mut self) -> &mut Self {
+        self.section = true;
+        self
+    }
+
+    /// スピーカーノートを設定する。ノートのページ (Deck::save_notes_to_bytes) でスライドの下に載せる
+    pub fn set_notes(&mut self, notes: Vec<Content>) -> &mut Self {
+        self.notes = notes;
+        self
+    }
+
+    /// このスライドに、既定のマスターの代わりに `name` のマスターを使う
+    pub fn set_master(&mut self, name: impl Into<String>) -> &mut Self {
+        self.master = MasterChoice::Named(name.into());
+        self
+    }
+
+    /// このスライドにはマスターの要素を描かない (表紙など)
+    pub fn without_master(&mut self) -> &mut Self {
+        self.master = MasterChoice::None;
+        self
+    }
+
+    /// このスライドだけ背景を変える
+    pub fn set_background(&mut self, background: Background) -> &mut Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// テキストブロックを、`layout` で指定したグリッド位置に追加する
+    pub fn add_text_block(&mut self, contents: Vec<Content>, layout: BlockLayout) -> &mut Self {
+        self.elements.push(Element::Text(TextBlock { contents, layout }));
+        self
+    }
+
+    /// 箇条書き・番号付きリストを、`layout` で指定したグリッド位置に追加する。
+    /// 項目は上から順に並び、`layout.width` は一番外側の項目の幅になる
+    pub fn add_list(&mut self, items: Vec<ListItem>, layout: BlockLayout) -> &mut Self {
+        self.add_flow(vec![FlowBlock::List(items)], layout)
+    }
+
+    /// 表を、`layout` で指定したグリッド位置 (表の左上) に追加する。
+    /// `layout.width` があれば表全体がその幅に収まるように列幅を決める
+    pub fn add_table(&mut self, table: Table, layout: BlockLayout) -> &mut Self {
+        self.add_flow(vec![FlowBlock::Table(table)], layout)
+    }
+
+    /// コードブロックを、`layout` で指定したグリッド位置 (枠の左上) に追加する。
+    /// 枠の幅は `layout.width` (無ければ一番長い行に合わせる)
+    pub fn add_code(&mut self, code: CodeBlock, layout: BlockLayout) -> &mut Self {
+        self.add_flow(vec![FlowBlock::Code(code)], layout)
+    }
+
+    /// リストや表を、`layout.start_row` から上から順に積み重ねて追加する
+    pub fn add_flow(&mut self, blocks: Vec<FlowBlock>, layout: BlockLayout) -> &mut Self {
+        self.elements.push(Element::Flow(Flow { blocks, layout }));
+        self
+    }
+
+    /// 画像ファイル (PNG/JPEG) を、`layout` で指定したグリッド上の枠に追加する。
+    /// ファイルは保存時に読み込まれる
+    pub fn add_image(&mut self, path: impl Into<PathBuf>, layout: ImageLayout) -> &mut Self {
+        self.elements.push(Element::Image(ImageBlock { path: path.into(), layout }));
+        self
+    }
+
+    /// 図形を追加する
+    pub fn add_shape(&mut self, shape: Shape) -> &mut Self {
+        self.elements.push(Element::Shape(shape));
+        self
+    }
+}
+
+/// 複数のスライドに繰り返し描く要素 (フッター、ロゴ、ページ番号など) の集まり。
+/// 背景の上、スライドの要素より奥に描く。
+/// テキストブロックの "{page}" "{pages}" "{date}" "{title}" は、ページごとに
+/// ページ番号・総ページ数・日付・デッキのタイトルに置き換える
+#[derive(Debug, Clone, Default)]
+pub struct Master {
+    elements: Vec<Element>,
+}
+
+impl Master {
+    pub fn add_text_block(&mut self, contents: Vec<Content>, layout: BlockLayout) -> &mut Self {
+        self.elements.push(Element::Text(TextBlock { contents, layout }));
+        self
+    }
+
+    pub fn add_image(&mut self, path: impl Into<PathBuf>, layout: ImageLayout) -> &mut Self {
+        self.elements.push(Element::Image(ImageBlock { path: path.into(), layout }));
+        self
+    }
+
+    pub fn add_shape(&mut self, shape: Shape) -> &mut Self {
+        self.elements.push(Element::Shape(shape));
+        self
+    }
+}
+
+/// マスターのテキストで、ページごとに置き換える文字列
+pub(crate) const PLACEHOLDERS: [&str; 4] = ["{page}", "{pages}", "{date}", "{title}"];
+
+/// ノートのページの大きさ (A4縦、pt)
+const NOTES_PAGE_SIZE: (f32, f32) = (595.28, 841.89);
+
+/// ノートのページの1グリッドの大きさ (ノートの本文の基準フォントサイズ、pt)
+const NOTES_BASE_SIZE: f32 = 12.0;
+
+/// ノートのページの上と左右の余白 (グリッド)
+const NOTES_MARGIN: f32 = 4.0;
+
+/// 指定の無いスライドに使うマスターの名前
+const DEFAULT_MASTER: &str = "default";
+
+/// スライドを集めて1つのPDFにするビルダー
+pub struct Deck {
+    doc: PdfDocument,
+    config: DrawConfig,
+    fonts: HashMap<FontStyle, LoadedFont>,
+    slides: Vec<Slide>,
+    masters: HashMap<String, Master>,
+    title: String,
+    date: String,
+}
+
+impl Deck {
+    /// `title` はPDFのタイトルになり、マスターの "{title}" にも使う
+    pub fn new(title: &str, config: DrawConfig) -> Self {
+        Deck {
+            doc: PdfDocument::new(title),
+            config,
+            fonts: HashMap::new(),
+            slides: Vec::new(),
+            masters: HashMap::new(),
+            title: title.to_string(),
+            date: today(),
+        }
+    }
+
+    /// `name` のマスターを登録する。"default" という名前のマスターは、指定の無いすべてのスライドに使う
+    pub fn set_master(&mut self, name: impl Into<String>, master: Master) -> &mut Self {
+        self.masters.insert(name.into(), master);
+        self
+    }
+
+    /// マスターの "{date}" に使う日付を変える (既定は今日の日付 YYYY-MM-DD、UTC)
+    pub fn set_date(&mut self, date: impl Into<String>) -> &mut Self {
+        self.date = date.into();
+        self
+    }
+
+    pub fn config(&self) -> &DrawConfig {
+        &self.config
+    }
+
+    /// `style` で使うフォントをファイルから読み込む。
+    /// 他のスタイルで読み込み済みのファイルなら、PDFに二重に埋め込まずにそれを使う
+    pub fn load_font(&mut self, style: FontStyle, path: &Path, warnings: &mut Vec<PdfWarnMsg>) -> Result<&mut Self, SlideError> {
+        let font = match self.fonts.values().find(|font| font.path == path) {
+            Some(font) => font.clone(),
+            None => load_font(&mut self.doc, path, warnings)?,
+        };
+        self.fonts.insert(style, font);
+        Ok(self)
+    }
+
+    /// 空のスライドを末尾に追加し、中身を組み立てるための参照を返す
+    pub fn add_slide(&mut self) -> &mut Slide {
+        self.slides.push(Slide::default());
+        self.slides.last_mut().expect("slide was just pushed")
+    }
+
+    /// Markdownのパース結果を、スライドファイルと同じ既定の配置でスライドとして追加する。
+    /// サブタイトルはタイトルのすぐ下に置き、本文はその下に続ける
+    pub fn add_markdown_slides(&mut self, sources: Vec<MarkdownSlide>) -> Result<(), ParseError> {
+        for source in sources {
+            let mut slide = Slide::default();
+            if let Some(directive) = &source.master {
+                self.apply_master(&mut slide, directive)?;
+            }
+            let layout = source.layout.as_ref();
+            self.place_content(&mut slide, layout, source.title, source.subtitle, source.columns, &source.column_breaks)?;
+            slide.set_notes(source.notes);
+            self.slides.push(slide);
+        }
+        Ok(())
+    }
+
+    /// タイトル・サブタイトル・本文の各段を、"@layout name=..." で選んだレイアウト (省略時は title-body) の枠に置く
+    fn place_content(
+        &self,
+        slide: &mut Slide,
+        layout: Option<&Directive>,
+        title: Option<Vec<Content>>,
+        subtitle: Option<Vec<Content>>,
+        columns: Vec<Vec<FlowBlock>>,
+        column_breaks: &[Directive],
+    ) -> Result<(), ParseError> {
+        let layout = match layout {
+            Some(directive) => parse_layout(directive)?,
+            None => SlideLayout::default(),
+        };
+        let regions = layout.regions(&self.config, subtitle.is_some());
+        if let Some(directive) = column_breaks.get(regions.columns.len() - 1) {
+            let message = format!("too many @column for layout '{}' (it has {} column(s))", layout.name(), regions.columns.len());
+            return Err(directive.error(directive.column, message));
+        }
+        for directive in column_breaks {
+            if let Some(arg) = directive.args.first() {
+                return Err(directive.error(arg.column, "@column takes no keys"));
+            }
+        }
+
+        // タイトルはそのまましおりの見出しにし、章の扉のレイアウトは章の区切りにする
+        if let Some(title) = title {
+            slide.set_bookmark(plain_text(&title));
+            slide.add_text_block(title, regions.title);
+        }
+        if layout == SlideLayout::SectionHeader {
+            slide.start_section();
+        }
+        if let Some(subtitle) = subtitle {
+            slide.add_text_block(subtitle, regions.subtitle);
+        }
+        for (blocks, region) in columns.into_iter().zip(regions.columns) {
+            slide.add_flow(blocks, region);
+        }
+        Ok(())
+    }
+
+    /// スライドファイルのパース結果を、既定の配置でスライドとして追加する。
+    /// タイトルは太字、本文はconfigの既定スタイルを起点にマークアップを解釈する。
+    /// 画像などの相対パスは `base_dir` (通常はスライドファイルのあるディレクトリ) から解決する。
+    pub fn add_parsed_slides(&mut self, sources: &[SlideSource], base_dir: &Path) -> Result<(), ParseError> {
+        for source in sources {
+            let mut slide = Slide::default();
+
+            if let Some(directive) = &source.background {
+                slide.set_background(parse_background(directive, &self.config, base_dir)?);
+            }
+            if let Some(directive) = &source.master {
+                self.apply_master(&mut slide, directive)?;
+            }
+            // 画像や図形は layer=back ならテキストより先に (奥に)、layer=front なら後に (手前に) 描く
+            let mut front: Vec<&Directive> = Vec::new();
+            for directive in &source.elements {
+                match directive.get("layer").map(|arg| (arg, arg.value.as_str())) {
+                    None | Some((_, "back")) => self.add_element(&mut slide, directive, base_dir)?,
+                    Some((_, "front")) => front.push(directive),
+                    Some((arg, value)) => {
+                        return Err(directive.error(arg.column, format!("unknown layer '{}' (expected back or front)", value)));
+                    }
+                }
+            }
+
+            let title = match &source.title {
+                Some(title) => Some(title.to_contents(&self.config, FontStyle::Bold, self.config.title_size_ratio)?),
+                None => None,
+            };
+            let subtitle = match &source.subtitle {
+                Some(subtitle) => Some(subtitle.to_contents(&self.config, self.config.default_font_style, self.config.subtitle_size_ratio)?),
+                None => None,
+            };
+            let columns = body_columns(source, &self.config)?;
+            self.place_content(&mut slide, source.layout.as_ref(), title, subtitle, columns, &source.column_breaks)?;
+
+            for directive in front {
+                self.add_element(&mut slide, directive, base_dir)?;
+            }
+
+            // ノートは1行ずつ本文と同じスタイルで解釈し、改行でつなぐ
+            let mut notes: Vec<Content> = Vec::new();
+            for (i, line) in source.notes.iter().enumerate() {
+                if i > 0 {
+                    notes.push(Content::Newline);
+                }
+                notes.extend(line.to_contents(&self.config, self.config.default_font_style, self.config.body_size_ratio)?);
+            }
+            slide.set_notes(notes);
+            self.slides.push(slide);
+        }
+        Ok(())
+    }
+
+    /// "@master name=マスター名|none" を解釈する。マスターは先に set_master で登録しておく
+    fn apply_master(&self, slide: &mut Slide, directive: &Directive) -> Result<(), ParseError> {
+        directive.check_keys(&["name"])?;
+        let arg = directive.require("name")?;
+        match arg.value.as_str() {
+            "none" => slide.without_master(),
+            name if self.masters.contains_key(name) => slide.set_master(name),
+            name => {
+                let mut known: Vec<&str> = self.masters.keys().map(String::as_str).collect();
+                known.sort();
+                known.push("none");
+                return Err(directive.error(arg.column, format!("unknown master '{}' (expected one of: {})", name, known.join(", "))));
+            }
+        };
+        Ok(())
+    }
+
+    /// @image や図形の指示を解釈してスライドに追加する
+    fn add_element(&self, slide: &mut Slide, directive: &Directive, base_dir: &Path) -> Result<(), ParseError> {
+        match directive.name.as_str() {
+            "image" => {
+                let (path, layout) = parse_image(directive, base_dir)?;
+                slide.add_image(path, layout);
+            }
+            _ => {
+                slide.add_shape(parse_shape(directive, &self.config)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// スライドやマスターの要素1つ分の描画命令を `page_ops` に追加する
+    fn draw_element(&self, page_ops: &mut Vec<DrawOp>, element: &Element) -> Result<(), SlideError> {
+        match element {
+            Element::Text(block) => {
+                draw_text_block(page_ops, &self.fonts, &self.config, &block.contents, &block.layout)?;
+            }
+            Element::Flow(flow) => {
+                let mut row = flow.layout.start_row;
+                for block in &flow.blocks {
+                    let layout = BlockLayout { start_row: row, ..flow.layout };
+                    row = match block {
+                        FlowBlock::List(items) => draw_list(page_ops, &self.fonts, &self.config, items, &layout)?,
+                        FlowBlock::Table(table) => draw_table(page_ops, &self.fonts, &self.config, table, &layout)?,
+                        FlowBlock::Code(code) => draw_code(page_ops, &self.fonts, &self.config, code, &layout)?,
+                    };
+                }
+            }
+            Element::Image(block) => {
+                let grid = self.config.base_font_size.0;
+                let layout = &block.layout;
+                let frame = Frame {
+                    x: layout.start_col * grid,
+                    y_top: self.config.page_height_pt.0 - layout.start_row * grid,
+                    width: layout.width * grid,
+                    height: layout.height * grid,
+                };
+                page_ops.push(DrawOp::Image { path: block.path.clone(), frame, fit: layout.fit });
+            }
+            Element::Shape(shape) => page_ops.extend(shape_ops(shape, &self.config)),
+        }
+        Ok(())
+    }
+
+    /// `index` 番目 (0始まり) のスライドに描くマスターの要素 ("{page}" などは置き換え済み)
+    fn master_elements(&self, index: usize, slide: &Slide) -> Result<Vec<Element>, SlideError> {
+        let master = match &slide.master {
+            MasterChoice::Default => self.masters.get(DEFAULT_MASTER),
+            MasterChoice::Named(name) => Some(self.masters.get(name).ok_or_else(|| SlideError::UnknownMaster(name.clone()))?),
+            MasterChoice::None => None,
+        };
+        let Some(master) = master else { return Ok(Vec::new()) };
+        let values = [(index + 1).to_string(), self.slides.len().to_string(), self.date.clone(), self.title.clone()];
+        let elements = master
+            .elements
+            .iter()
+            .map(|element| match element {
+                Element::Text(block) => Element::Text(TextBlock { contents: fill_placeholders(&block.contents, &values), layout: block.layout }),
+                other => other.clone(),
+            })
+            .collect();
+        Ok(elements)
+    }
+
+    /// `index` 番目 (0始まり) のスライドを、出力形式によらない描画命令のページにする
+    fn layout_page(&self, index: usize, slide: &Slide) -> Result<Page, SlideError> {
+        let (width, height) = (self.config.page_width_pt.0, self.config.page_height_pt.0);
+        let mut page_ops: Vec<DrawOp> = Vec::new();
+        // 背景は他の要素より先に描く
+        let background = slide.background.as_ref().unwrap_or(&self.config.background);
+        let whole_page = Frame { x: 0.0, y_top: height, width, height };
+        if let Some(color) = background.color {
+            page_ops.push(fill_rect(whole_page, color));
+        }
+        if let Some(image) = &background.image {
+            page_ops.push(DrawOp::Image { path: image.path.clone(), frame: whole_page, fit: image.fit });
+        }
+        // マスターは背景の上、スライドの要素より奥に描く
+        for element in self.master_elements(index, slide)?.iter().chain(&slide.elements) {
+            self.draw_element(&mut page_ops, element)?;
+        }
+        // スライドへのリンクは、飛び先のページがあるか確かめる
+        for op in &page_ops {
+            if let DrawOp::Link { target: LinkTarget::Slide(target), .. } = op
+                && *target > self.slides.len()
+            {
+                return Err(SlideError::BrokenLink { slide: index + 1, target: *target, slides: self.slides.len() });
+            }
+        }
+        Ok(Page { width, height, ops: page_ops })
+    }
+
+    /// すべてのスライドをレイアウトし、1枚ずつ `renderer` に渡す
+    fn render(&self, renderer: &mut impl Renderer) -> Result<(), SlideError> {
+        for (index, slide) in self.slides.iter().enumerate() {
+            renderer.render_page(&self.layout_page(index, slide)?)?;
+        }
+        Ok(())
+    }
+
+    /// すべてのスライドを描画し、PDFのバイト列にする
+    pub fn save_to_bytes(&self, warnings: &mut Vec<PdfWarnMsg>) -> Result<Vec<u8>, SlideError> {
+        // 1スライド = 1ページ。画像は複製したドキュメントに登録する (何度でも保存できるようにするため)
+        let mut renderer = PdfRenderer::new(self.doc.clone(), &self.fonts, warnings);
+        self.render(&mut renderer)?;
+        self.finish_pdf(renderer.save())
+    }
+
+    /// スライドごとに、縮小したスライドとその下にスピーカーノートを載せたA4縦のページ (ノートのページ) のPDFを作る。
+    /// しおりはスライドのPDFと同じものを付ける
+    pub fn save_notes_to_bytes(&self, warnings: &mut Vec<PdfWarnMsg>) -> Result<Vec<u8>, SlideError> {
+        let mut renderer = PdfRenderer::new(self.doc.clone(), &self.fonts, warnings);
+        for (index, slide) in self.slides.iter().enumerate() {
+            renderer.render_page(&self.notes_page(index, slide)?)?;
+        }
+        self.finish_pdf(renderer.save())
+    }
+
+    /// `index` 番目 (0始まり) のスライドのノートのページ。
+    /// ノートは NOTES_BASE_SIZE を1グリッドとして、スライドと同じテキストブロックの仕組みで折り返して描く
+    fn notes_page(&self, index: usize, slide: &Slide) -> Result<Page, SlideError> {
+        let (width, height) = NOTES_PAGE_SIZE;
+        let mut config = self.config.clone();
+        config.page_width_pt = Pt(width);
+        config.page_height_pt = Pt(height);
+        config.base_font_size = Pt(NOTES_BASE_SIZE);
+
+        // スライドは余白の内側の幅いっぱいに縮小して上に置き、はみ出した部分は描かない
+        let content_width = width / NOTES_BASE_SIZE - 2.0 * NOTES_MARGIN;
+        let slide_page = self.layout_page(index, slide)?;
+        let scale = content_width * NOTES_BASE_SIZE / slide_page.width;
+        let frame = Frame {
+            x: NOTES_MARGIN * NOTES_BASE_SIZE,
+            y_top: height - NOTES_MARGIN * NOTES_BASE_SIZE,
+            width: content_width * NOTES_BASE_SIZE,
+            height: slide_page.height * scale,
+        };
+        let mut page_ops = vec![DrawOp::PushClip(frame)];
+        page_ops.extend(slide_page.ops.iter().map(|op| op.scaled(scale, frame.x, frame.y_top - frame.height)));
+        page_ops.push(DrawOp::PopClip);
+        // 縮小したスライドの縁に細い灰色の枠を描く (背景が白いスライドでも範囲が分かるように)
+        page_ops.push(stroke_rect(frame, Stroke { color: SlideColor::Custom(0.6, 0.6, 0.6), width: 0.5, dash: Vec::new() }));
+
+        // ノートはスライドの下に、余白の内側の幅で折り返す
+        let notes_row = NOTES_MARGIN + frame.height / NOTES_BASE_SIZE + 2.0;
+        let layout = BlockLayout::at(NOTES_MARGIN, notes_row).with_width(content_width);
+        draw_text_block(&mut page_ops, &self.fonts, &config, &slide.notes, &layout)?;
+        Ok(Page { width, height, ops: page_ops })
+    }
+
+    /// 保存したPDFを手直しする (リンクの注釈をページに移し、しおりの見出しがあればしおりを付け足す)
+    fn finish_pdf(&self, bytes: Vec<u8>) -> Result<Vec<u8>, SlideError> {
+        let entries: Vec<OutlineEntry> = self
+            .slides
+            .iter()
+            .enumerate()
+            .filter(|(_, slide)| slide.bookmark.is_some() || slide.section)
+            .map(|(page, slide)| OutlineEntry { title: slide.bookmark.clone(), page, section: slide.section })
+            .collect();
+        finish_pdf(&bytes, &entries)
+    }
+
+    /// すべてのスライドを、1枚ずつSVG文書にする。ファイルに書くときは svg_file_name の名前で同じディレクトリに置く
+    pub fn save_to_svg(&self, fonts: SvgFonts) -> Result<Vec<String>, SlideError> {
+        let mut renderer = SvgRenderer::new(&self.fonts, fonts, self.slides.len());
+        self.render(&mut renderer)?;
+        Ok(renderer.finish())
+    }
+
+    /// すべてのスライドを、1つのHTMLファイルで見られるプレゼンテーションにする (フォントと画像も埋め込む)
+    pub fn save_to_html(&self) -> Result<String, SlideError> {
+        let mut renderer = HtmlRenderer::new(&self.fonts);
+        self.render(&mut renderer)?;
+        renderer.finish(&self.title)
+    }
+
+    /// すべてのスライドを、解像度 `dpi` で1枚ずつPNGにする。ファイルに書くときは png_file_name の名前を使う
+    pub fn save_to_png(&self, dpi: f32) -> Result<Vec<Vec<u8>>, SlideError> {
+        let mut renderer = PngRenderer::new(&self.fonts, dpi)?;
+        self.render(&mut renderer)?;
+        renderer.finish().iter().map(encode_png).collect()
+    }
+
+    /// すべてのスライドを解像度 `dpi` で描き、横に `columns` 枚ずつ並べた1枚のPNGにする
+    pub fn save_contact_sheet(&self, dpi: f32, columns: usize) -> Result<Vec<u8>, SlideError> {
+        let mut renderer = PngRenderer::new(&self.fonts, dpi)?;
+        self.render(&mut renderer)?;
+        // 余白は基準フォントサイズの半分
+        let gap = (self.config.base_font_size.0 / 2.0 * dpi / 72.0).round() as u32;
+        encode_png(&contact_sheet(&renderer.finish(), columns, gap)?)
+    }
+
+    /// すべてのスライドを、PowerPointで編集できるPPTXファイルのバイト列にする。
+    /// テキストブロックはテキストボックスになり、リストや表などは1行ずつの文字と図形で置く
+    pub fn save_to_pptx(&self) -> Result<Vec<u8>, SlideError> {
+        let (width, height) = (self.config.page_width_pt.0, self.config.page_height_pt.0);
+        let mut writer = PptxWriter::new(&self.fonts, width, height)?;
+        for (index, slide) in self.slides.iter().enumerate() {
+            let background = slide.background.as_ref().unwrap_or(&self.config.background);
+            let mut items: Vec<PptxItem> = Vec::new();
+            if let Some(image) = &background.image {
+                let whole_page = Frame { x: 0.0, y_top: height, width, height };
+                items.push(PptxItem::Draw(DrawOp::Image { path: image.path.clone(), frame: whole_page, fit: image.fit }));
+            }
+            for element in self.master_elements(index, slide)?.iter().chain(&slide.elements) {
+                match element {
+                    Element::Text(block) => {
+                        // 高さを知るために一度描いてみる
+                        let end_row = draw_text_block(&mut Vec::new(), &self.fonts, &self.config, &block.contents, &block.layout)?;
+                        items.push(text_box(&self.config, &block.contents, &block.layout, end_row));
+                    }
+                    other => {
+                        let mut ops = Vec::new();
+                        self.draw_element(&mut ops, other)?;
+                        items.extend(ops.into_iter().map(PptxItem::Draw));
+                    }
+                }
+            }
+            writer.add_slide(background.color, &items)?;
+        }
+        writer.finish(&self.title)
+    }
+}
+
+/// マスターのテキストの "{page}" などを、PLACEHOLDERS と同じ順に並べた `values` で置き換える
+fn fill_placeholders(contents: &[Content], values: &[String; PLACEHOLDERS.len()]) -> Vec<Content> {
+    contents
+        .iter()
+        .map(|content| match content {
+            Content::Span(span) => {
+                let mut span = span.clone();
+                for (placeholder, value) in PLACEHOLDERS.iter().zip(values) {
+                    span.text = span.text.replace(placeholder, value);
+                }
+                Content::Span(span)
+            }
+            Content::Newline => Content::Newline,
+        })
+        .collect()
+}
+
+/// しおりの見出し用に、テキストの中身だけをつなげる (改行は空白にする)
+fn plain_text(contents: &[Content]) -> String {
+    let text: Vec<&str> = contents
+        .iter()
+        .map(|content| match content {
+            Content::Span(span) => span.text.as_str(),
+            Content::Newline => " ",
+        })
+        .collect();
+    text.concat().trim().to_string()
+}
+
+/// 今日の日付を YYYY-MM-DD で返す。ローカルの時差は見ずに UTC で数えるので、日本時間の0時〜9時は前日の日付になる
+fn today() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+    civil_date((seconds / 86_400) as i64)
+}
+
+/// 1970-01-01 からの日数を、グレゴリオ暦の日付 YYYY-MM-DD に直す。
+/// 3月始まりの年で数えると閏日が年末に来て計算が楽になる
+fn civil_date(days_since_epoch: i64) -> String {
+    let days = days_since_epoch + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153; // 0 = 3月
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// 本文の行の種類
+#[derive(PartialEq)]
+enum BodyLine {
+    Fence,
+    TableRow,
+    Text,
+}
+
+fn body_line_kind(line: &SourceLine) -> BodyLine {
+    if line.text.starts_with(CODE_FENCE) {
+        BodyLine::Fence
+    } else if split_table_row(&line.text).is_some() {
+        BodyLine::TableRow
+    } else {
+        BodyLine::Text
+    }
+}
+
+/// 本文を @column の位置で段に分け、段ごとにブロックに変換する。段の先頭と末尾の空行は捨てる
+fn body_columns(source: &SlideSource, config: &DrawConfig) -> Result<Vec<Vec<FlowBlock>>, ParseError> {
+    let mut columns = Vec::new();
+    let mut options = source.tables.iter().peekable();
+    let mut rest = &source.body[..];
+    for end_line in source.column_breaks.iter().map(|directive| directive.line).chain([usize::MAX]) {
+        let (lines, next) = rest.split_at(rest.iter().take_while(|line| line.line < end_line).count());
+        let is_blank = |line: &SourceLine| line.text.is_empty();
+        let lines = &lines[lines.iter().take_while(|line| is_blank(line)).count()..];
+        let lines = &lines[..lines.len() - lines.iter().rev().take_while(|line| is_blank(line)).count()];
+        columns.push(body_blocks(lines, &mut options, config)?);
+        // 段の中の @table は、その段の表に使う
+        if let Some(directive) = options.next_if(|directive| directive.line < end_line) {
+            return Err(directive.error(directive.column, "@table must be followed by a table"));
+        }
+        rest = next;
+    }
+    Ok(columns)
+}
+
+/// 本文を、"```" で囲まれた範囲 (コードブロック)、"|" で始まる行の並び (表)、それ以外の行の並び (リスト) に分ける。
+/// リストは1行を1項目とし、記号の無い行はただの段落として扱う
+fn body_blocks<'a>(
+    body: &[SourceLine],
+    options: &mut Peekable<impl Iterator<Item = &'a Directive>>,
+    config: &DrawConfig,
+) -> Result<Vec<FlowBlock>, ParseError> {
+    let mut blocks: Vec<FlowBlock> = Vec::new();
+    let mut rest = body;
+    while let Some(first) = rest.first() {
+        let kind = body_line_kind(first);
+        let run = match kind {
+            // 閉じのフェンスまで (閉じ忘れはパーサーがエラーにしている)
+            BodyLine::Fence => rest[1..].iter().position(|line| line.text.trim_end() == CODE_FENCE).map_or(rest.len(), |p| p + 2),
+            _ => rest.iter().take_while(|line| body_line_kind(line) == kind).count(),
+        };
+        let (lines, next) = rest.split_at(run);
+        match kind {
+            BodyLine::Fence => blocks.push(FlowBlock::Code(parse_code(lines)?)),
+            BodyLine::TableRow => {
+                // 表の前に書かれた @table はその表に使う
+                let option = options.next_if(|directive| directive.line < first.line);
+                blocks.push(FlowBlock::Table(parse_table(lines, option, config)?));
+            }
+            BodyLine::Text => blocks.push(FlowBlock::List(list_items(lines, config)?)),
+        }
+        rest = next;
+    }
+    Ok(blocks)
+}
+
+/// 本文の各行を、リストの項目に変換する。
+/// 入れ子は直前の項目より2段以上深くならないよう詰める
+fn list_items(lines: &[SourceLine], config: &DrawConfig) -> Result<Vec<ListItem>, ParseError> {
+    let mut items: Vec<ListItem> = Vec::new();
+    for line in lines {
+        let in_list = items.last().is_some_and(|item| item.marker.is_some());
+        let item = match parse_list_line(&line.text, in_list) {
+            Some((level, marker, skipped)) => {
+                let max_level = if in_list { items.last().map_or(0, |item| item.level + 1) } else { 0 };
+                let text = SourceLine { text: line.text.chars().skip(skipped).collect(), column: line.column + skipped, ..line.clone() };
+                let contents = text.to_contents(config, config.default_font_style, config.body_size_ratio)?;
+                ListItem { level: level.min(max_level), marker: Some(marker), contents }
+            }
+            None => {
+                let contents = line.to_contents(config, config.default_font_style, config.body_size_ratio)?;
+                ListItem { level: 0, marker: None, contents }
+            }
+        };
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// "```rust numbers highlight=2-3" から閉じの "```" までをコードブロックにする
+fn parse_code(lines: &[SourceLine]) -> Result<CodeBlock, ParseError> {
+    let open = &lines[0];
+    let mut code = parse_fence_info(&open.text[CODE_FENCE.len()..]).map_err(|word| ParseError {
+        line: open.line,
+        column: open.column,
+        message: format!("unknown code block option '{}' (expected numbers or highlight=2-3,5)", word),
+    })?;
+
+    let end = if lines.len() > 1 && lines[lines.len() - 1].text.trim_end() == CODE_FENCE { lines.len() - 1 } else { lines.len() };
+    let source: Vec<&str> = lines[1..end].iter().map(|line| line.text.as_str()).collect();
+    code.source = source.join("\n");
+    Ok(code)
+}
+
+/// テスト用に、同梱のフォントを読み込んだ 32x18 グリッド (1グリッド = 24pt) の空のデッキを作る
+#[cfg(test)]
+pub(crate) fn test_deck() -> Deck {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fonts");
+    let mut deck = Deck::new("test", DrawConfig::from_grid(24.0, 32.0, 18.0));
+    let mut warnings = Vec::new();
+    for (style, file) in [
+        (FontStyle::Regular, "RictyDiminished-Regular.ttf"),
+        (FontStyle::Bold, "RictyDiminished-Bold.ttf"),
+        (FontStyle::Italic, "RictyDiminished-Regular.ttf"),
+        (FontStyle::Mono, "RictyDiminished-Regular.ttf"),
+    ] {
+        deck.load_font(style, &dir.join(file), &mut warnings).expect("bundled font");
+    }
+    deck
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::span_width;
+    use crate::{Link, LinkTarget, NamedColor, TextSpan};
+    use crate::font::font_for;
+    use crate::parser::parse_deck;
+    use lopdf::{Dictionary, Document, Object, ObjectId};
+
+    fn span(text: &str, link: Option<&str>) -> TextSpan {
+        let link = link.map(|target| Link { target: LinkTarget::parse(target).unwrap(), underline: true });
+        TextSpan { text: text.to_string(), style: FontStyle::Regular, size_ratio: 1.0, color: SlideColor::Named(NamedColor::Black), link }
+    }
+
+    /// 注釈の辞書の /Rect を [左, 下, 右, 上] で返す
+    fn rect(annot: &Dictionary) -> Vec<f32> {
+        annot.get(b"Rect").unwrap().as_array().unwrap().iter().map(|n| n.as_float().unwrap()).collect()
+    }
+
+    /// しおりの1項目 (ページ番号は1始まり)
+    #[derive(Debug, PartialEq)]
+    struct Bookmark {
+        title: String,
+        page: u32,
+        children: Vec<Bookmark>,
+    }
+
+    fn bookmark(title: &str, page: u32, children: Vec<Bookmark>) -> Bookmark {
+        Bookmark { title: title.to_string(), page, children }
+    }
+
+    /// `parent` の下のしおりを First/Next でたどる。Parent・Prev・Last・Count のつながりもあわせて確かめる
+    fn bookmarks(pdf: &Document, parent: ObjectId) -> Vec<Bookmark> {
+        let pages: HashMap<ObjectId, u32> = pdf.get_pages().into_iter().map(|(number, id)| (id, number)).collect();
+        let parent_dict = pdf.get_dictionary(parent).unwrap();
+        let mut items = Vec::new();
+        let mut previous: Option<ObjectId> = None;
+        let mut next = parent_dict.get(b"First").and_then(Object::as_reference).ok();
+        while let Some(id) = next {
+            let item = pdf.get_dictionary(id).unwrap();
+            assert_eq!(item.get(b"Parent").unwrap().as_reference().unwrap(), parent);
+            assert_eq!(item.get(b"Prev").and_then(Object::as_reference).ok(), previous);
+            let action = pdf.get_dictionary(item.get(b"A").unwrap().as_reference().unwrap()).unwrap();
+            let dest = action.get(b"D").unwrap().as_array().unwrap();
+            let title = String::from_utf8(item.get(b"Title").unwrap().as_str().unwrap().to_vec()).unwrap();
+            items.push(bookmark(&title, pages[&dest[0].as_reference().unwrap()], bookmarks(pdf, id)));
+            previous = Some(id);
+            next = item.get(b"Next").and_then(Object::as_reference).ok();
+        }
+        assert_eq!(parent_dict.get(b"Last").and_then(Object::as_reference).ok(), previous);
+        if !items.is_empty() {
+            assert_eq!(parent_dict.get(b"Count").unwrap().as_i64().unwrap(), items.len() as i64);
+        }
+        items
+    }
+
+    #[test]
+    fn section_slides_become_parent_bookmarks() {
+        let mut deck = test_deck();
+        let text = "# Intro\n---\n@layout name=section\n# Part A\n---\n# A1\n---\n# A2\n---\n@layout name=section\n# Part B\n---\n# B1\n";
+        deck.add_parsed_slides(&parse_deck(text).unwrap(), Path::new(".")).unwrap();
+        let pdf = Document::load_mem(&deck.save_to_bytes(&mut Vec::new()).unwrap()).unwrap();
+        let catalog = pdf.catalog().unwrap();
+        assert_eq!(catalog.get(b"PageMode").unwrap().as_name().unwrap(), b"UseOutlines");
+        let outlines = catalog.get(b"Outlines").unwrap().as_reference().unwrap();
+        assert_eq!(
+            bookmarks(&pdf, outlines),
+            [
+                bookmark("Intro", 1, Vec::new()),
+                bookmark("Part A", 2, vec![bookmark("A1", 3, Vec::new()), bookmark("A2", 4, Vec::new())]),
+                bookmark("Part B", 5, vec![bookmark("B1", 6, Vec::new())]),
+            ]
+        );
+    }
+
+    #[test]
+    fn notes_pages_are_a4_and_wrap_inside_the_margins() {
+        let mut deck = test_deck();
+        let long = "ノートは余白の内側で折り返す。 Long notes wrap within the text area of the page. ".repeat(4);
+        let text = format!("# With notes\n@notes\n{}\n---\n# Without notes\n", long);
+        deck.add_parsed_slides(&parse_deck(&text).unwrap(), Path::new(".")).unwrap();
+
+        let pdf = Document::load_mem(&deck.save_notes_to_bytes(&mut Vec::new()).unwrap()).unwrap();
+        let pages = pdf.get_pages();
+        // ノートの無いスライドにもページを作る
+        assert_eq!(pages.len(), 2);
+        for page in pages.values() {
+            let media_box: Vec<f32> =
+                pdf.get_dictionary(*page).unwrap().get(b"MediaBox").unwrap().as_array().unwrap().iter().map(|n| n.as_float().unwrap()).collect();
+            assert_eq!(media_box.iter().map(|v| v.round()).collect::<Vec<f32>>(), [0.0, 0.0, 595.0, 842.0]);
+        }
+
+        // ノートの文字は縮小したスライドの枠より後に描かれる
+        let page = deck.notes_page(0, &deck.slides[0]).unwrap();
+        let frame_end = page.ops.iter().rposition(|op| matches!(op, DrawOp::PopClip)).unwrap();
+        let (left, right) = (NOTES_MARGIN * NOTES_BASE_SIZE, NOTES_PAGE_SIZE.0 - NOTES_MARGIN * NOTES_BASE_SIZE);
+        let mut baselines = Vec::new();
+        for op in &page.ops[frame_end..] {
+            if let DrawOp::Text { x, baseline, style, size, text, .. } = op {
+                let width = font_for(&deck.fonts, *style).unwrap().text_width_em(text) * size;
+                assert!(*x >= left - 0.01 && x + width <= right + 0.01, "'{}' at {}..{} is outside {}..{}", text, x, x + width, left, right);
+                if !baselines.contains(baseline) {
+                    baselines.push(*baseline);
+                }
+            }
+        }
+        assert!(baselines.len() > 1, "notes were not wrapped");
+    }
+
+    #[test]
+    fn link_annotations_cover_spans_and_point_at_slides() {
+        let mut deck = test_deck();
+        let spans = [span("Go ", None), span("次へ", Some("#3")), span(" and ", None), span("web", Some("https://example.com"))];
+        deck.add_slide().add_text_block(spans.iter().cloned().map(Content::Span).collect(), BlockLayout::at(2.0, 3.0));
+        for text in ["two", "three"] {
+            deck.add_slide().add_text_block(vec![Content::Span(span(text, None))], BlockLayout::at(1.0, 1.0));
+        }
+
+        // 期待する範囲: Spanを左から順に測って並べた箱 (上端は3行目、高さは文字サイズの1.2倍)
+        let widths: Vec<f32> = spans.iter().map(|span| span_width(&deck.fonts, span).unwrap() * 24.0).collect();
+        let top = 18.0 * 24.0 - 3.0 * 24.0;
+        let left = |index: usize| 2.0 * 24.0 + widths[..index].iter().sum::<f32>();
+        let expected = |index: usize| vec![left(index), top - 24.0 * 1.2, left(index) + widths[index], top];
+
+        let pdf = Document::load_mem(&deck.save_to_bytes(&mut Vec::new()).unwrap()).unwrap();
+        let pages = pdf.get_pages();
+        // 注釈はページの辞書から参照されていないとビューアーに見えない
+        let annots: Vec<&Dictionary> = pdf
+            .get_page_annotations(pages[&1])
+            .unwrap()
+            .into_iter()
+            .filter(|annot| annot.get(b"Subtype").and_then(Object::as_name).is_ok_and(|name| name == b"Link"))
+            .collect();
+        assert_eq!(annots.len(), 2);
+
+        let close = |actual: Vec<f32>, expected: Vec<f32>| {
+            assert!(actual.iter().zip(&expected).all(|(a, e)| (a - e).abs() < 0.01), "{:?} != {:?}", actual, expected);
+        };
+        close(rect(annots[0]), expected(1));
+        close(rect(annots[1]), expected(3));
+
+        let action = |annot: &Dictionary| pdf.dereference(annot.get(b"A").unwrap()).unwrap().1.as_dict().unwrap().clone();
+        let go_to = action(annots[0]);
+        assert_eq!(go_to.get(b"S").unwrap().as_name().unwrap(), b"GoTo");
+        let dest = go_to.get(b"D").unwrap().as_array().unwrap();
+        assert_eq!(dest[0].as_reference().unwrap(), pages[&3]);
+
+        let uri = action(annots[1]);
+        assert_eq!(uri.get(b"S").unwrap().as_name().unwrap(), b"URI");
+        assert_eq!(uri.get(b"URI").unwrap().as_str().unwrap(), b"https://example.com");
+    }
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_date(0), "1970-01-01");
+        assert_eq!(civil_date(-1), "1969-12-31");
+        assert_eq!(civil_date(11_016), "2000-02-29");
+        assert_eq!(civil_date(20_088), "2024-12-31");
+        // 2100年は100で割り切れるので閏年ではない
+        assert_eq!(civil_date(47_540), "2100-02-28");
+        assert_eq!(civil_date(47_541), "2100-03-01");
+        assert_eq!(civil_date(-135_080), "1600-03-01");
+    }
+}
@@ -0,0 +1,70 @@
+// --- スライド生成中に起こりうるエラー ---
+
+use crate::FontStyle;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum SlideError {
+    /// フォントファイルが読めない (存在しない、権限が無いなど)
+    FontNotFound { path: PathBuf, source: io::Error },
+    /// フォントファイルは読めたが、フォントとして解釈できない
+    FontParse { path: PathBuf },
+    /// 描画しようとしたFontStyleのフォントが読み込まれていない
+    UnknownStyle(FontStyle),
+    /// 画像ファイルが読めない
+    ImageRead { path: PathBuf, source: io::Error },
+    /// 画像ファイルをPNG/JPEGとして解釈できない
+    ImageDecode { path: PathBuf, message: String },
+    /// テーマファイルが読めない
+    ThemeRead { path: PathBuf, source: io::Error },
+    /// テーマファイルの書式や値が正しくない
+    ThemeInvalid { path: PathBuf, message: String },
+    /// スライドに指定されたマスターが登録されていない
+    UnknownMaster(String),
+    /// リンクの飛び先のスライドが無い (スライド番号は1始まり)
+    BrokenLink { slide: usize, target: usize, slides: usize },
+    /// 保存したPDFを読み直して、リンクの注釈を移したり、しおりを付けたりできない
+    PostProcess(String),
+    /// ページをPNGに描けない (解像度が大きすぎるなど)
+    Raster(String),
+    /// PPTXファイルを書けない
+    Pptx(String),
+    /// 出力先のディレクトリを作成できない
+    OutputDir { path: PathBuf, source: io::Error },
+}
+
+impl fmt::Display for SlideError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlideError::FontNotFound { path, .. } => write!(f, "cannot read font file {}", path.display()),
+            SlideError::FontParse { path } => write!(f, "cannot parse font file {}", path.display()),
+            SlideError::UnknownStyle(style) => write!(f, "no font loaded for style {:?}", style),
+            SlideError::ImageRead { path, .. } => write!(f, "cannot read image file {}", path.display()),
+            SlideError::ImageDecode { path, message } => write!(f, "cannot decode image file {}: {}", path.display(), message),
+            SlideError::ThemeRead { path, .. } => write!(f, "cannot read theme file {}", path.display()),
+            SlideError::ThemeInvalid { path, message } => write!(f, "invalid theme file {}: {}", path.display(), message),
+            SlideError::UnknownMaster(name) => write!(f, "no master named '{}'", name),
+            SlideError::BrokenLink { slide, target, slides } => {
+                write!(f, "slide {} links to slide {}, but the deck has {} slide(s)", slide, target, slides)
+            }
+            SlideError::PostProcess(message) => write!(f, "cannot post-process saved PDF: {}", message),
+            SlideError::Raster(message) => write!(f, "cannot rasterize slides: {}", message),
+            SlideError::Pptx(message) => write!(f, "cannot write PPTX: {}", message),
+            SlideError::OutputDir { path, .. } => write!(f, "cannot create output directory {}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for SlideError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SlideError::FontNotFound { source, .. }
+            | SlideError::ImageRead { source, .. }
+            | SlideError::ThemeRead { source, .. }
+            | SlideError::OutputDir { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::error::SlideError;
+use crate::FontStyle;
+use printpdf::{FontId, ParsedFont, PdfDocument, PdfWarnMsg};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// PDFに登録したフォントIDと、文字幅の計測に使うパース済みフォントの組
+#[derive(Clone)]
+pub(crate) struct LoadedFont {
+    pub(crate) id: FontId,
+    pub(crate) parsed: ParsedFont,
+    pub(crate) path: PathBuf,
+}
+
+impl LoadedFont {
+    /// グリフの送り幅 (フォント単位) をhmtxテーブルから直接読む
+    fn glyph_advance(&self, glyph_index: u16) -> u16 {
+        let num_h_metrics = self.parsed.hhea_table.as_ref().map_or(0, |hhea| hhea.num_h_metrics as usize);
+        if num_h_metrics == 0 {
+            return self.parsed.font_metrics.units_per_em;
+        }
+        // numberOfHMetrics 以降のグリフは、最後のエントリの送り幅を共有する
+        let offset = (glyph_index as usize).min(num_h_metrics - 1) * 4;
+        match self.parsed.hmtx_data.get(offset..offset + 2) {
+            Some(bytes) => u16::from_be_bytes([bytes[0], bytes[1]]),
+            None => self.parsed.font_metrics.units_per_em,
+        }
+    }
+
+    /// テキストの送り幅を em 単位 (フォントサイズ = 1.0) で返す
+    pub(crate) fn text_width_em(&self, text: &str) -> f32 {
+        let units_per_em = self.parsed.font_metrics.units_per_em as f32;
+        let total_units: u32 = text
+            .chars()
+            // cmapに無い文字は .notdef (グリフ0) として描かれる
+            .map(|c| self.glyph_advance(self.parsed.lookup_glyph_index(c as u32).unwrap_or(0)) as u32)
+            .sum();
+        total_units as f32 / units_per_em
+    }
+}
+
+pub(crate) fn load_font(doc: &mut PdfDocument, path: &Path, warns: &mut Vec<PdfWarnMsg>) -> Result<LoadedFont, SlideError> {
+    let bytes: Vec<u8> = fs::read(path).map_err(|source| SlideError::FontNotFound { path: path.to_path_buf(), source })?;
+    let parsed: ParsedFont = ParsedFont::from_bytes(&bytes, 0, warns).ok_or_else(|| SlideError::FontParse { path: path.to_path_buf() })?;
+    Ok(LoadedFont { id: doc.add_font(&parsed), parsed, path: path.to_path_buf() })
+}
+
+/// 指定されたスタイルのフォントを取り出す
+pub(crate) fn font_for(fonts: &HashMap<FontStyle, LoadedFont>, style: FontStyle) -> Result<&LoadedFont, SlideError> {
+    fonts.get(&style).ok_or(SlideError::UnknownStyle(style))
+}
+
+/// テスト用に、同梱の Ricty Diminished (半角 0.5em・全角 1em) を全スタイルに読み込む。斜体と等幅は Regular で代用する
+#[cfg(test)]
+pub(crate) fn test_fonts() -> HashMap<FontStyle, LoadedFont> {
+    let mut doc = PdfDocument::new("test");
+    let mut warnings = Vec::new();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fonts");
+    let regular = load_font(&mut doc, &dir.join("RictyDiminished-Regular.ttf"), &mut warnings).expect("bundled regular font");
+    let bold = load_font(&mut doc, &dir.join("RictyDiminished-Bold.ttf"), &mut warnings).expect("bundled bold font");
+    HashMap::from([
+        (FontStyle::Regular, regular.clone()),
+        (FontStyle::Bold, bold),
+        (FontStyle::Italic, regular.clone()),
+        (FontStyle::Mono, regular),
+    ])
+}
@@ -0,0 +1,124 @@
+// --- HTMLのプレゼンテーションへの出力 ---
+//
+// すべてのスライドを1つのHTMLファイルにまとめる。スライド1枚が1つの <section> で、
+// 中身は svg.rs と同じ <svg> 要素 (viewBox はページと同じpt単位なので、配置はPDFと同じグリッドになる)。
+// フォントはデッキ全体で使う文字だけを取り出し、文書の先頭で一度だけ @font-face として埋め込む。
+// 矢印キー・スペース・PageUp/PageDown・Home/End でページを送り、f キーで全画面にする。
+
+use crate::error::SlideError;
+use crate::font::LoadedFont;
+use crate::page::{Page, Renderer};
+use crate::svg::{escape, SvgFonts, SvgWriter, UsedChars};
+use crate::FontStyle;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// ページ送りと全画面の切り替え。表示中のスライドは URL の "#slide-N" で決める
+const SCRIPT: &str = r##"(function () {
+  var slides = document.querySelectorAll("section.slide");
+  var current = 0;
+  function show(index) {
+    current = Math.max(0, Math.min(slides.length - 1, index));
+    slides.forEach(function (slide, i) { slide.classList.toggle("active", i === current); });
+    if (location.hash !== "#" + slides[current].id) history.replaceState(null, "", "#" + slides[current].id);
+  }
+  function fromHash() {
+    var match = /^#slide-(\d+)$/.exec(location.hash);
+    show(match ? parseInt(match[1], 10) - 1 : current);
+  }
+  document.addEventListener("keydown", function (event) {
+    if (event.altKey || event.ctrlKey || event.metaKey) return;
+    switch (event.key) {
+      case "ArrowRight": case "ArrowDown": case "PageDown": case " ": case "Enter": show(current + 1); break;
+      case "ArrowLeft": case "ArrowUp": case "PageUp": case "Backspace": show(current - 1); break;
+      case "Home": show(0); break;
+      case "End": show(slides.length - 1); break;
+      case "f": case "F":
+        if (document.fullscreenElement) document.exitFullscreen();
+        else document.documentElement.requestFullscreen();
+        break;
+      default: return;
+    }
+    event.preventDefault();
+  });
+  window.addEventListener("hashchange", fromHash);
+  fromHash();
+})();
+"##;
+
+/// ページを <section> にしていき、最後に1つのHTML文書にまとめる
+pub(crate) struct HtmlRenderer<'a> {
+    writer: SvgWriter<'a>,
+    used: UsedChars,
+    sections: String,
+    pages: usize,
+    width: f32,
+    height: f32,
+}
+
+impl<'a> HtmlRenderer<'a> {
+    pub(crate) fn new(fonts: &'a HashMap<FontStyle, LoadedFont>) -> Self {
+        HtmlRenderer { writer: SvgWriter::new(fonts), used: UsedChars::new(), sections: String::new(), pages: 0, width: 0.0, height: 0.0 }
+    }
+
+    /// `title` を <title> にしたHTML文書
+    pub(crate) fn finish(self, title: &str) -> Result<String, SlideError> {
+        let fonts = self.writer.font_faces(&self.used, SvgFonts::Embed)?;
+        let mut html = String::new();
+        let _ = writeln!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">");
+        let _ = writeln!(html, "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">");
+        let _ = writeln!(html, "<title>{}</title>\n<style>", escape(title));
+        html.push_str(&fonts);
+        // スライドはページの縦横比のまま、画面いっぱいに拡大する
+        let _ = writeln!(html, "html, body {{ margin: 0; height: 100%; background: #000; overflow: hidden; }}");
+        let _ = writeln!(html, "section.slide {{ display: none; width: 100vw; height: 100vh; align-items: center; justify-content: center; }}");
+        let _ = writeln!(html, "section.slide.active {{ display: flex; }}");
+        let _ = writeln!(
+            html,
+            "section.slide > svg {{ width: min(100vw, 100vh * {w} / {h}); height: min(100vh, 100vw * {h} / {w}); background: #fff; }}",
+            w = self.width,
+            h = self.height
+        );
+        let _ = writeln!(html, "@media print {{ html, body {{ overflow: visible; }} section.slide {{ display: flex; break-after: page; }} }}");
+        let _ = writeln!(html, "</style>\n</head>\n<body>");
+        html.push_str(&self.sections);
+        let _ = writeln!(html, "<script>\n{}</script>\n</body>\n</html>", SCRIPT);
+        Ok(html)
+    }
+}
+
+impl Renderer for HtmlRenderer<'_> {
+    fn render_page(&mut self, page: &Page) -> Result<(), SlideError> {
+        self.writer.collect_chars(page, &mut self.used)?;
+        (self.width, self.height) = (page.width, page.height);
+        self.pages += 1;
+        // スライドへのリンクは同じ文書の中のアンカーにする
+        let svg = self.writer.svg_element(page, "", |slide| format!("#slide-{}", slide))?;
+        let _ = writeln!(self.sections, "<section class=\"slide\" id=\"slide-{}\">\n{}</section>", self.pages, svg);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::deck::test_deck;
+    use crate::parser::parse_deck;
+    use std::path::Path;
+
+    #[test]
+    fn one_section_per_slide_and_fonts_once() {
+        let mut deck = test_deck();
+        let slides = parse_deck("# One\n{link=#3}to three{/link}\n---\n# Two\n**bold**\n---\n# Three\nplain\n").unwrap();
+        deck.add_parsed_slides(&slides, Path::new(".")).unwrap();
+        let html = deck.save_to_html().unwrap();
+
+        for number in 1..=3 {
+            assert_eq!(html.matches(&format!("<section class=\"slide\" id=\"slide-{}\">", number)).count(), 1);
+        }
+        assert_eq!(html.matches("<section").count(), 3);
+        // フォントは文書の先頭の <style> に、使ったフォントごとに1つずつ (スライドごとではない)
+        assert_eq!(html.matches("<style>").count(), 1);
+        assert_eq!(html.matches("@font-face").count(), 2);
+        assert!(html.contains("<a href=\"#slide-3\">"));
+    }
+}
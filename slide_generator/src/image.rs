@@ -0,0 +1,114 @@
+// --- ラスター画像 (PNG/JPEG) の読み込みと配置 ---
+
+use crate::error::SlideError;
+use crate::page::Frame;
+use crate::pdf::rect_polygon;
+use printpdf::{Op, PaintMode, PdfDocument, PdfWarnMsg, Pt, RawImage, XObjectId, XObjectTransform};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 画像を枠にどう収めるか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFit {
+    Contain, // 縦横比を保ち、枠に全体が収まるように縮小・拡大する (余白ができる)
+    Cover,   // 縦横比を保ち、枠を覆うように拡大する (はみ出した部分は切り取る)
+    Stretch, // 縦横比を無視して枠いっぱいに引き伸ばす
+}
+
+impl ImageFit {
+    pub fn from_name(name: &str) -> Option<ImageFit> {
+        match name {
+            "contain" => Some(ImageFit::Contain),
+            "cover" => Some(ImageFit::Cover),
+            "stretch" => Some(ImageFit::Stretch),
+            _ => None,
+        }
+    }
+}
+
+/// 画像の配置 (単位はグリッド)。(start_col, start_row) が枠の左上
+#[derive(Debug, Clone, Copy)]
+pub struct ImageLayout {
+    pub start_col: f32,
+    pub start_row: f32,
+    pub width: f32,
+    pub height: f32,
+    pub fit: ImageFit,
+}
+
+impl ImageLayout {
+    /// 左上 (col, row) から幅 width ・高さ height の枠。既定では Contain で収める
+    pub fn at(col: f32, row: f32, width: f32, height: f32) -> Self {
+        ImageLayout { start_col: col, start_row: row, width, height, fit: ImageFit::Contain }
+    }
+
+    pub fn with_fit(mut self, fit: ImageFit) -> Self {
+        self.fit = fit;
+        self
+    }
+}
+
+/// PDFに登録済みの画像
+#[derive(Debug, Clone)]
+pub(crate) struct PlacedImage {
+    id: XObjectId,
+    width_px: f32,
+    height_px: f32,
+}
+
+/// 同じファイルを何度も埋め込まないよう、パスごとに登録結果を覚えておく
+#[derive(Default)]
+pub(crate) struct ImageCache {
+    images: HashMap<PathBuf, PlacedImage>,
+}
+
+impl ImageCache {
+    /// 画像ファイルを読み込んでPDFに登録する (登録済みならそれを返す)
+    pub(crate) fn load(&mut self, doc: &mut PdfDocument, path: &Path, warnings: &mut Vec<PdfWarnMsg>) -> Result<PlacedImage, SlideError> {
+        if let Some(image) = self.images.get(path) {
+            return Ok(image.clone());
+        }
+        let bytes = fs::read(path).map_err(|source| SlideError::ImageRead { path: path.to_path_buf(), source })?;
+        let raw = RawImage::decode_from_bytes(&bytes, warnings)
+            .map_err(|message| SlideError::ImageDecode { path: path.to_path_buf(), message })?;
+        let image = PlacedImage { id: doc.add_image(&raw), width_px: raw.width as f32, height_px: raw.height as f32 };
+        self.images.insert(path.to_path_buf(), image.clone());
+        Ok(image)
+    }
+}
+
+/// 縦横 width_px x height_px の画像を `frame` に `fit` で収めたときに、画像を描く範囲 (枠の中央に置く)
+pub(crate) fn fit_frame(frame: Frame, fit: ImageFit, width_px: f32, height_px: f32) -> Frame {
+    let fit_x = frame.width / width_px;
+    let fit_y = frame.height / height_px;
+    let (scale_x, scale_y) = match fit {
+        ImageFit::Contain => (fit_x.min(fit_y), fit_x.min(fit_y)),
+        ImageFit::Cover => (fit_x.max(fit_y), fit_x.max(fit_y)),
+        ImageFit::Stretch => (fit_x, fit_y),
+    };
+    let (width, height) = (width_px * scale_x, height_px * scale_y);
+    Frame { x: frame.x + (frame.width - width) / 2.0, y_top: frame.y_top - (frame.height - height) / 2.0, width, height }
+}
+
+/// PDFに登録済みの画像を `frame` に `fit` で収めて描く命令を作る
+pub(crate) fn image_ops(image: &PlacedImage, frame: Frame, fit: ImageFit) -> Vec<Op> {
+    let drawn = fit_frame(frame, fit, image.width_px, image.height_px);
+    let mut ops = vec![Op::SaveGraphicsState];
+    if fit == ImageFit::Cover {
+        ops.push(Op::DrawPolygon { polygon: rect_polygon(frame, PaintMode::Clip) });
+    }
+    ops.push(Op::UseXobject {
+        id: image.id.clone(),
+        transform: XObjectTransform {
+            translate_x: Some(Pt(drawn.x)),
+            translate_y: Some(Pt(drawn.y_top - drawn.height)),
+            scale_x: Some(drawn.width / image.width_px),
+            scale_y: Some(drawn.height / image.height_px),
+            dpi: Some(72.0), // 1px = 1pt として扱い、倍率は scale で指定する
+            rotate: None,
+        },
+    });
+    ops.push(Op::RestoreGraphicsState);
+    ops
+}
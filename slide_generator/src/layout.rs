@@ -0,0 +1,89 @@
+// --- 名前付きのスライドレイアウト ---
+//
+// タイトル・サブタイトル・本文の枠の位置 (グリッド単位) を、名前を付けてまとめたもの。
+// 枠はページのグリッド数から決まり、左右には2グリッドずつ余白を取る。
+
+use crate::{BlockLayout, DrawConfig, HAlign, VAlign};
+
+/// スライドのレイアウト
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SlideLayout {
+    /// 表紙: タイトル・サブタイトル・本文をページの中央に揃える
+    Title,
+    /// 上にタイトル、その下に本文
+    #[default]
+    TitleBody,
+    /// 上にタイトル、その下に本文を左右2段で
+    TwoColumn,
+    /// 章の扉: タイトルをページの中ほどに置く
+    SectionHeader,
+}
+
+/// レイアウトの各枠。本文は columns に左から順に流し込む
+#[derive(Debug, Clone)]
+pub struct LayoutRegions {
+    pub title: BlockLayout,
+    pub subtitle: BlockLayout,
+    pub columns: Vec<BlockLayout>,
+}
+
+/// 左右の余白と、2段組みの段の間の空き
+const MARGIN: f32 = 2.0;
+const COLUMN_GAP: f32 = 1.0;
+
+impl SlideLayout {
+    /// "title" "title-body" "two-column" "section" から決める (知らない名前はNone)
+    pub fn from_name(name: &str) -> Option<SlideLayout> {
+        match name {
+            "title" => Some(SlideLayout::Title),
+            "title-body" => Some(SlideLayout::TitleBody),
+            "two-column" => Some(SlideLayout::TwoColumn),
+            "section" => Some(SlideLayout::SectionHeader),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SlideLayout::Title => "title",
+            SlideLayout::TitleBody => "title-body",
+            SlideLayout::TwoColumn => "two-column",
+            SlideLayout::SectionHeader => "section",
+        }
+    }
+
+    /// `config` のページでの各枠を返す。サブタイトルがあるときは、本文をその分だけ下げる
+    pub fn regions(self, config: &DrawConfig, has_subtitle: bool) -> LayoutRegions {
+        let width = config.grid_cols() - MARGIN * 2.0;
+        let middle = config.grid_rows() / 2.0;
+        let body = |row: f32, h_align: HAlign| {
+            BlockLayout::at(MARGIN, row).with_width(width).with_line_spacing(config.line_spacing_ratio).with_h_align(h_align)
+        };
+        // タイトルの行とサブタイトルの行 (本文はサブタイトルがあればその下、無ければサブタイトルの位置から)
+        let (title_row, subtitle_row, h_align) = match self {
+            SlideLayout::Title => (middle - 3.0, middle - 0.5, HAlign::Center),
+            SlideLayout::TitleBody | SlideLayout::TwoColumn => (2.0, 4.5, HAlign::Left),
+            SlideLayout::SectionHeader => (middle - 2.0, middle + 0.5, HAlign::Left),
+        };
+        let body_row = match self {
+            SlideLayout::TitleBody | SlideLayout::TwoColumn if !has_subtitle => 5.0,
+            _ if has_subtitle => subtitle_row + 2.5,
+            _ => subtitle_row,
+        };
+
+        let columns = match self {
+            SlideLayout::TwoColumn => {
+                let column_width = (width - COLUMN_GAP) / 2.0;
+                let left = body(body_row, h_align).with_width(column_width);
+                let right = BlockLayout { start_col: MARGIN + column_width + COLUMN_GAP, ..left };
+                vec![left, right]
+            }
+            _ => vec![body(body_row, h_align)],
+        };
+        LayoutRegions {
+            title: BlockLayout::at(MARGIN, title_row).with_width(width).with_align(VAlign::Bottom).with_h_align(h_align),
+            subtitle: BlockLayout::at(MARGIN, subtitle_row).with_width(width).with_h_align(h_align),
+            columns,
+        }
+    }
+}
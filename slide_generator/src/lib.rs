@@ -0,0 +1,57 @@
+//! グリッド座標でスライドを組み立て、PDF (またはスピーカーノート付きのノートのページのPDF、スライドごとのSVG・PNG、1ファイルのHTML・PPTX) として出力するライブラリ。
+//!
+//! 1グリッド = 基準フォントサイズ (既定 24pt) の正方形で、ページは既定で 32x18 グリッド。
+//!
+//! ```no_run
+//! use slide_generator::{BlockLayout, Content, Deck, DrawConfig, FontStyle, SlideColor, NamedColor, TextSpan};
+//! use std::path::Path;
+//!
+//! # fn main() -> Result<(), slide_generator::SlideError> {
+//! let mut warnings = Vec::new();
+//! let mut deck = Deck::new("Example", DrawConfig::from_grid(24.0, 32.0, 18.0));
+//! deck.load_font(FontStyle::Regular, Path::new("fonts/RictyDiminished-Regular.ttf"), &mut warnings)?;
+//!
+//! let hello = TextSpan { text: "こんにちは".to_string(), style: FontStyle::Regular, size_ratio: 1.0, color: SlideColor::Named(NamedColor::Black), link: None };
+//! deck.add_slide().add_text_block(vec![Content::Span(hello)], BlockLayout::at(2.0, 2.0));
+//! let pdf_bytes = deck.save_to_bytes(&mut warnings)?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod code;
+mod color;
+mod deck;
+mod error;
+mod font;
+mod html;
+mod image;
+mod layout;
+mod linebreak;
+mod list;
+pub mod markdown;
+pub mod markup;
+mod outline;
+mod page;
+pub mod parser;
+mod pdf;
+mod pptx;
+mod raster;
+mod shape;
+mod svg;
+mod table;
+mod text;
+mod theme;
+
+pub use code::{CodeBlock, CodeLanguage, CodeStyle, TokenKind};
+pub use color::{NamedColor, SlideColor};
+pub use deck::{Background, BackgroundImage, Deck, FlowBlock, Master, Slide};
+pub use error::SlideError;
+pub use image::{ImageFit, ImageLayout};
+pub use layout::{LayoutRegions, SlideLayout};
+pub use list::{ListItem, ListMarker};
+pub use raster::png_file_name;
+pub use shape::{ArrowHeads, Shape, ShapeGeometry};
+pub use svg::{svg_file_name, SvgFonts};
+pub use table::{ColumnWidth, Table, TableCell, TableStyle};
+pub use text::{BlockLayout, Content, DrawConfig, FontStyle, HAlign, Link, LinkTarget, TextSpan, VAlign};
+pub use theme::Theme;
@@ -0,0 +1,67 @@
+// --- 行分割の規則 (日本語の禁則処理と、欧文の単語境界) ---
+
+/// 行頭に来てはいけない文字 (行頭禁則)
+const NO_LINE_START: &str = "、。，．・：；？！゛゜ヽヾゝゞ々ー～…‥〜）」』］｝〕〉》】〙〗〟’”｠»ぁぃぅぇぉっゃゅょゎゕゖァィゥェォッャュョヮヵヶ)]},.:;!?%";
+
+/// 行末に来てはいけない文字 (行末禁則)
+const NO_LINE_END: &str = "（「『［｛〔〈《【〘〖〝‘“｟«([{";
+
+/// CJKの文字 (文字と文字の間で改行してよいもの) かどうか
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3000..=0x30FF   // CJKの記号・句読点, ひらがな, カタカナ
+        | 0x3400..=0x4DBF // CJK統合漢字拡張A
+        | 0x4E00..=0x9FFF // CJK統合漢字
+        | 0xF900..=0xFAFF // CJK互換漢字
+        | 0xFF00..=0xFFEF // 全角英数・半角カナ
+    )
+}
+
+/// `prev` と `next` の間で改行してよいかどうかを返す
+pub fn can_break_between(prev: char, next: char) -> bool {
+    // 空白の直後は常に改行可能 (空白そのものは行末に残す)
+    if prev.is_whitespace() {
+        return !next.is_whitespace();
+    }
+    if next.is_whitespace() {
+        return false;
+    }
+    if NO_LINE_START.contains(next) || NO_LINE_END.contains(prev) {
+        return false;
+    }
+    // 欧文同士は単語の途中になるので改行しない
+    is_cjk(prev) || is_cjk(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cjk_and_latin() {
+        assert!(can_break_between('日', '本'));
+        assert!(can_break_between('a', '日'));
+        assert!(can_break_between('日', 'a'));
+        assert!(!can_break_between('a', 'b'));
+    }
+
+    #[test]
+    fn whitespace_stays_at_the_line_end() {
+        assert!(can_break_between(' ', 'a'));
+        assert!(can_break_between('　', '日'));
+        assert!(!can_break_between('a', ' '));
+        assert!(!can_break_between(' ', ' '));
+    }
+
+    #[test]
+    fn prohibited_line_start_and_end() {
+        for next in ['、', '。', '」', 'ー', 'っ', 'ッ', '…', ')', '!'] {
+            assert!(!can_break_between('あ', next), "{}", next);
+        }
+        for prev in ['「', '（', '『', '('] {
+            assert!(!can_break_between(prev, 'あ'), "{}", prev);
+        }
+        assert!(can_break_between('」', 'あ'));
+        assert!(can_break_between('あ', '「'));
+    }
+}
@@ -0,0 +1,331 @@
+// --- 箇条書き・番号付きリスト ---
+//
+// 各項目は draw_text_block でテキストブロックとして描く。
+// 記号の幅は実際のフォントで測り、項目のテキスト (2行目以降も含む) は記号の右側に揃える。
+// 入れ子の項目は、親の項目のテキストの開始位置から記号を書き始める。
+
+use crate::error::SlideError;
+use crate::font::LoadedFont;
+use crate::page::DrawOp;
+use crate::text::{draw_text_block, span_width};
+use crate::{BlockLayout, Content, DrawConfig, FontStyle, TextSpan};
+use std::collections::HashMap;
+
+/// 記号とテキストの間の空き (グリッド単位、項目の文字サイズに比例)
+const MARKER_GAP: f32 = 0.5;
+
+/// 項目の頭に付ける記号・番号の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListMarker {
+    Dot,     // ・
+    Bullet,  // •
+    Dash,    // –
+    Decimal, // 1. 2. 3.
+    Alpha,   // a) b) c)
+    Circled, // ① ② ③
+    Roman,   // (i) (ii) (iii)
+}
+
+impl ListMarker {
+    /// 同じ並びの中で `number` 番目 (1始まり) の項目に付ける記号
+    pub fn label(self, number: usize) -> String {
+        match self {
+            ListMarker::Dot => "・".to_string(),
+            ListMarker::Bullet => "•".to_string(),
+            ListMarker::Dash => "–".to_string(),
+            ListMarker::Decimal => format!("{}.", number),
+            ListMarker::Alpha => format!("{})", alpha(number)),
+            // ①〜⑳ より先は丸数字が無いので括弧で代用する
+            ListMarker::Circled => match number {
+                1..=20 => char::from_u32(0x2460 + number as u32 - 1).map(String::from).unwrap_or_default(),
+                _ => format!("({})", number),
+            },
+            ListMarker::Roman => format!("({})", roman(number)),
+        }
+    }
+
+    fn is_numbered(self) -> bool {
+        !matches!(self, ListMarker::Dot | ListMarker::Bullet | ListMarker::Dash)
+    }
+}
+
+/// リストの1項目。marker が None の項目は記号の無い段落として扱い、番号の並びもそこで途切れる
+#[derive(Debug, Clone)]
+pub struct ListItem {
+    pub level: usize, // 入れ子の深さ (0が一番外側)
+    pub marker: Option<ListMarker>,
+    pub contents: Vec<Content>,
+}
+
+/// リストを `layout` の位置から描画し、次に使える行の位置を返す。
+/// `layout.width` は一番外側の項目の幅で、入れ子の項目はその分だけ狭くなる。
+pub(crate) fn draw_list(
+    ops: &mut Vec<DrawOp>,
+    fonts: &HashMap<FontStyle, LoadedFont>,
+    config: &DrawConfig,
+    items: &[ListItem],
+    layout: &BlockLayout,
+) -> Result<f32, SlideError> {
+    // --- 1. 番号を振り、記号を作る ---
+    let labels = number_items(items);
+    let marker_spans: Vec<Option<TextSpan>> = items
+        .iter()
+        .zip(&labels)
+        .map(|(item, label)| label.as_ref().map(|label| marker_span(item, label, config)))
+        .collect();
+
+    // --- 2. 深さごとのぶら下げ幅 (記号の幅 + 空き) を、その深さで一番幅の広い記号に合わせる ---
+    let max_level = items.iter().map(|item| item.level).max().unwrap_or(0);
+    let mut hanging = vec![0.0f32; max_level + 1];
+    for (item, span) in items.iter().zip(&marker_spans) {
+        if let Some(span) = span {
+            let width = span_width(fonts, span)? + MARKER_GAP * span.size_ratio;
+            hanging[item.level] = hanging[item.level].max(width);
+        }
+    }
+    // 深さ n の記号は、深さ n-1 のテキストの開始位置から書き始める
+    let mut text_cols = Vec::with_capacity(hanging.len());
+    let mut col = layout.start_col;
+    for width in &hanging {
+        col += width;
+        text_cols.push(col);
+    }
+
+    // --- 3. 描画 ---
+    let mut row = layout.start_row;
+    for (item, span) in items.iter().zip(marker_spans) {
+        let text_col = match item.marker {
+            Some(_) => text_cols[item.level],
+            None => item.level.checked_sub(1).map_or(layout.start_col, |parent| text_cols[parent]),
+        };
+        if let Some(span) = span {
+            let marker_col = text_col - hanging[item.level];
+            let marker_layout = BlockLayout { start_col: marker_col, start_row: row, width: None, ..*layout };
+            draw_text_block(ops, fonts, config, &[Content::Span(span)], &marker_layout)?;
+        }
+
+        let text_layout = BlockLayout {
+            start_col: text_col,
+            start_row: row,
+            width: layout.width.map(|width| (width - (text_col - layout.start_col)).max(0.0)),
+            ..*layout
+        };
+        let next_row = draw_text_block(ops, fonts, config, &item.contents, &text_layout)?;
+        // 中身が空の項目も1行分の高さを取る
+        row = if next_row > row { next_row } else { row + layout.line_spacing_ratio };
+    }
+    Ok(row)
+}
+
+/// 各項目の記号を決める。番号は同じ深さ・同じ種類の項目が続く間だけ数え上げる
+fn number_items(items: &[ListItem]) -> Vec<Option<String>> {
+    // 深さごとの (記号の種類, 直前の番号)
+    let mut counters: Vec<(ListMarker, usize)> = Vec::new();
+    items
+        .iter()
+        .map(|item| {
+            let Some(marker) = item.marker else {
+                counters.truncate(item.level);
+                return None;
+            };
+            counters.truncate(item.level + 1);
+            let number = match counters.get_mut(item.level) {
+                Some((previous, count)) if *previous == marker => {
+                    *count += 1;
+                    *count
+                }
+                _ => {
+                    counters.resize(item.level + 1, (marker, 0));
+                    counters[item.level] = (marker, 1);
+                    1
+                }
+            };
+            Some(if marker.is_numbered() { marker.label(number) } else { marker.label(1) })
+        })
+        .collect()
+}
+
+/// 記号は項目の先頭のSpanと同じ大きさ・色で、太字にはしない
+fn marker_span(item: &ListItem, label: &str, config: &DrawConfig) -> TextSpan {
+    let first = item.contents.iter().find_map(|content| match content {
+        Content::Span(span) => Some(span),
+        Content::Newline => None,
+    });
+    TextSpan {
+        text: label.to_string(),
+        style: config.default_font_style,
+        size_ratio: first.map_or(config.body_size_ratio, |span| span.size_ratio),
+        color: first.map_or(config.default_color, |span| span.color),
+        link: None,
+    }
+}
+
+/// 1 → a, 26 → z, 27 → aa
+fn alpha(mut number: usize) -> String {
+    let mut letters = Vec::new();
+    while number > 0 {
+        number -= 1;
+        letters.push((b'a' + (number % 26) as u8) as char);
+        number /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+/// 小文字のローマ数字 (0は表せないので "0" のまま)
+fn roman(mut number: usize) -> String {
+    if number == 0 {
+        return "0".to_string();
+    }
+    const TABLE: [(usize, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    let mut result = String::new();
+    for (value, symbol) in TABLE {
+        while number >= value {
+            result.push_str(symbol);
+            number -= value;
+        }
+    }
+    result
+}
+
+/// スライドファイルの本文1行がリスト項目なら (深さ, 記号, 記号の後ろのテキストが始まる文字位置) を返す。
+/// 深さは行頭の空白2つ (タブは1つ) で1段。記号の直後には空白が必要。
+/// "-" は深さに応じて • – ・ を順に使い、番号付きの項目の番号は書かれた数字によらず振り直す。
+/// "(c) 2024" や "2024. was" のような文を項目にしないよう、番号付きの記号は最初の番号 ("1." "a)" "①" "(i)") か、
+/// 直前の行がリストの項目 (`in_list`) のときだけ項目の記号とみなす。
+/// 記号を文字のまま書くときは "\(c)" のように "\" を前に付ける (マークアップのエスケープで "\" は消える)。
+pub(crate) fn parse_list_line(text: &str, in_list: bool) -> Option<(usize, ListMarker, usize)> {
+    let content = text.trim_start_matches([' ', '\t']);
+    let indent = &text[..text.len() - content.len()];
+    let level = indent.chars().map(|c| if c == '\t' { 2 } else { 1 }).sum::<usize>() / 2;
+
+    let (label, rest) = content.split_once(' ')?;
+    let (marker, first) = match label {
+        "-" => ([ListMarker::Bullet, ListMarker::Dash, ListMarker::Dot][level % 3], true),
+        "・" => (ListMarker::Dot, true),
+        "•" => (ListMarker::Bullet, true),
+        "–" => (ListMarker::Dash, true),
+        _ if label.len() > 1 && label.ends_with('.') && label[..label.len() - 1].bytes().all(|b| b.is_ascii_digit()) => {
+            (ListMarker::Decimal, label == "1.")
+        }
+        _ if label.len() == 2 && label.ends_with(')') && label.as_bytes()[0].is_ascii_lowercase() => (ListMarker::Alpha, label == "a)"),
+        _ if label.chars().count() == 1 && ('①'..='⑳').contains(&label.chars().next()?) => (ListMarker::Circled, label == "①"),
+        _ if label.len() > 2
+            && label.starts_with('(')
+            && label.ends_with(')')
+            && label[1..label.len() - 1].chars().all(|c| "ivxlcdm".contains(c)) =>
+        {
+            (ListMarker::Roman, label == "(i)")
+        }
+        _ => return None,
+    };
+    if !first && !in_list {
+        return None;
+    }
+    let skipped = text.len() - rest.trim_start().len();
+    Some((level, marker, text[..skipped].chars().count()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup::parse_inline;
+
+    fn text_of(contents: &[Content]) -> String {
+        contents
+            .iter()
+            .map(|content| match content {
+                Content::Span(span) => span.text.as_str(),
+                Content::Newline => "\n",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bullets_start_a_list_anywhere() {
+        assert_eq!(parse_list_line("- item", false), Some((0, ListMarker::Bullet, 2)));
+        assert_eq!(parse_list_line("  - item", false), Some((1, ListMarker::Dash, 4)));
+        assert_eq!(parse_list_line("\t\t- item", false), Some((2, ListMarker::Dot, 4)));
+        assert_eq!(parse_list_line("・ 項目", false), Some((0, ListMarker::Dot, 2)));
+        assert_eq!(parse_list_line("-   spaced", false), Some((0, ListMarker::Bullet, 4)));
+    }
+
+    #[test]
+    fn first_ordinal_starts_a_list() {
+        assert_eq!(parse_list_line("1. one", false), Some((0, ListMarker::Decimal, 3)));
+        assert_eq!(parse_list_line("a) one", false), Some((0, ListMarker::Alpha, 3)));
+        assert_eq!(parse_list_line("① one", false), Some((0, ListMarker::Circled, 2)));
+        assert_eq!(parse_list_line("  (i) one", false), Some((1, ListMarker::Roman, 6)));
+    }
+
+    #[test]
+    fn later_ordinals_only_continue_a_list() {
+        assert_eq!(parse_list_line("2. two", true), Some((0, ListMarker::Decimal, 3)));
+        assert_eq!(parse_list_line("b) two", true), Some((0, ListMarker::Alpha, 3)));
+        assert_eq!(parse_list_line("② two", true), Some((0, ListMarker::Circled, 2)));
+        assert_eq!(parse_list_line("(ii) two", true), Some((0, ListMarker::Roman, 5)));
+
+        assert_eq!(parse_list_line("(c) 2024 ACME", false), None);
+        assert_eq!(parse_list_line("2024. was a good year", false), None);
+        assert_eq!(parse_list_line("b) is the answer", false), None);
+    }
+
+    #[test]
+    fn prose_is_not_a_list_item() {
+        assert_eq!(parse_list_line("-item", false), None);
+        assert_eq!(parse_list_line("1.5 times", true), None);
+        assert_eq!(parse_list_line("ab) two letters", true), None);
+        assert_eq!(parse_list_line("(x2) mixed", true), None);
+        assert_eq!(parse_list_line("plain text", true), None);
+        assert_eq!(parse_list_line("-", false), None);
+    }
+
+    #[test]
+    fn backslash_escapes_a_leading_marker() {
+        for line in ["\\1. not a list", "\\- not a list", "\\a) not a list", "\\(i) not a list"] {
+            assert_eq!(parse_list_line(line, true), None, "{}", line);
+        }
+        let config = DrawConfig::from_grid(24.0, 32.0, 18.0);
+        let contents = parse_inline("\\(c) 2024 ACME", &config, FontStyle::Regular, 1.0).unwrap();
+        assert_eq!(text_of(&contents), "(c) 2024 ACME");
+    }
+
+    #[test]
+    fn numbering_restarts_per_level_and_marker() {
+        let item = |level, marker| ListItem { level, marker, contents: Vec::new() };
+        let items = [
+            item(0, Some(ListMarker::Decimal)),
+            item(1, Some(ListMarker::Alpha)),
+            item(1, Some(ListMarker::Alpha)),
+            item(0, Some(ListMarker::Decimal)),
+            item(1, Some(ListMarker::Roman)),
+            item(0, None),
+            item(0, Some(ListMarker::Decimal)),
+            item(0, Some(ListMarker::Bullet)),
+        ];
+        let labels: Vec<Option<String>> = number_items(&items);
+        let labels: Vec<Option<&str>> = labels.iter().map(Option::as_deref).collect();
+        assert_eq!(labels, [Some("1."), Some("a)"), Some("b)"), Some("2."), Some("(i)"), None, Some("1."), Some("•")]);
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!(ListMarker::Alpha.label(28), "ab)");
+        assert_eq!(ListMarker::Roman.label(1994), "(mcmxciv)");
+        assert_eq!(ListMarker::Circled.label(20), "⑳");
+        assert_eq!(ListMarker::Circled.label(21), "(21)");
+    }
+}
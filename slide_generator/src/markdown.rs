@@ -0,0 +1,569 @@
+// --- Markdown 形式のスライドファイル (.md) のパーサー ---
+//
+// 書式:
+//   ---                 : この行だけでスライドを区切る (コードブロックの中は除く)
+//   # タイトル          : スライドのタイトル
+//   ## サブタイトル     : タイトルの下に添える小さめの見出し
+//   ### 見出し          : 3段目以下の見出しは太字の段落
+//   段落                : 本文 (空行で区切った段落やリストの間は1行空ける)
+//   **太字** *斜体* `コード` : 文字のスタイル (画像と HTML は無視する)
+//   [文字](https://...) : リンク ("#3" なら3枚目のスライドへ。色と下線はテーマの [links] に従う)
+//   - 項目 / 1. 項目    : 箇条書き・番号付きリスト (入れ子も可。番号は書かれた数字によらず1から振る)
+//   ```言語 numbers highlight=2-3
+//   ...
+//   ```                 : コードブロック (情報文字列の書式は slides.txt と同じ)
+//   | a | b |           : 表 (GFM の表。見出し行と ":" による揃え方を使う)
+//   <!-- @master name=マスター名|none -->
+//   <!-- @layout name=two-column -->
+//   <!-- @column -->    : slides.txt の @master @layout @column と同じ (HTMLコメントの中に書く)
+//   <!-- notes: ... --> : スピーカーノート (複数行に分けて書ける。マークアップは解釈せず、そのままの文字として扱う。
+//                         段落の途中に書いたコメントは空行で段落が切れるので、空行を挟むならコメントだけの行に書く)
+//
+// どの要素も slides.txt と同じ中間表現 (Content / TextSpan / FlowBlock) に変換する。
+
+use crate::code::parse_fence_info;
+use crate::parser::{parse_directive, Directive, ParseError};
+use crate::{CodeBlock, CodeLanguage, Content, DrawConfig, FlowBlock, FontStyle, HAlign, Link, LinkTarget, ListItem, ListMarker, Table, TableCell, TextSpan};
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::ops::Range;
+
+const SLIDE_SEPARATOR: &str = "---";
+
+/// Markdownのスライド1枚分
+#[derive(Debug, Clone)]
+pub struct MarkdownSlide {
+    pub title: Option<Vec<Content>>,
+    pub subtitle: Option<Vec<Content>>,
+    pub columns: Vec<Vec<FlowBlock>>, // 本文の段 (@column で次の段に移る)
+    pub master: Option<Directive>,
+    pub layout: Option<Directive>,
+    pub column_breaks: Vec<Directive>,
+    pub notes: Vec<Content>, // スピーカーノート (ノートのページに載せる)
+}
+
+/// Markdownファイル全体を読み取り、スライドのリストに変換する。
+/// タイトルは太字、本文はconfigの既定スタイルを起点にする
+pub fn parse_markdown(input: &str, config: &DrawConfig) -> Result<Vec<MarkdownSlide>, ParseError> {
+    split_slides(input)?.into_iter().map(|(text, first_line)| SlideBuilder::new(config, text, first_line).build()).collect()
+}
+
+/// "---" だけの行でスライドごとの範囲に分け、(範囲のテキスト, 範囲の先頭の行番号) を返す。
+/// Markdownでは段落の直後の "---" が見出しの下線になってしまうので、パースする前に分けておく
+fn split_slides(input: &str) -> Result<Vec<(&str, usize)>, ParseError> {
+    let mut slides = Vec::new();
+    let mut start = 0; // 今のスライドが始まるバイト位置
+    let mut start_line = 1;
+    let mut offset = 0;
+    let mut fence: Option<(String, usize)> = None; // 開いているコードフェンス ("```" や "~~~~") とその行番号
+
+    for (index, line) in input.split_inclusive('\n').enumerate() {
+        let line_no = index + 1;
+        let trimmed = line.trim_end();
+        let indented = trimmed.trim_start();
+        match &fence {
+            // 閉じのフェンスは、開きと同じ文字で同じ長さ以上
+            Some((open, _)) => {
+                if indented.starts_with(open.as_str()) && indented.chars().all(|c| open.starts_with(c)) {
+                    fence = None;
+                }
+            }
+            None if trimmed == SLIDE_SEPARATOR => {
+                slides.push((&input[start..offset], start_line));
+                start = offset + line.len();
+                start_line = line_no + 1;
+            }
+            None => {
+                let marker = indented.chars().next().filter(|c| *c == '`' || *c == '~');
+                if let Some(marker) = marker {
+                    let run = indented.chars().take_while(|c| *c == marker).count();
+                    if run >= 3 && line.len() - line.trim_start().len() < 4 {
+                        fence = Some((marker.to_string().repeat(run), line_no));
+                    }
+                }
+            }
+        }
+        offset += line.len();
+    }
+    if let Some((open, line)) = fence {
+        return Err(ParseError { line, column: 1, message: format!("unterminated code block (missing closing {})", open) });
+    }
+    // 最後の区切り以降に何も書かれていなければ、空のスライドは作らない
+    if !input[start..].trim().is_empty() || slides.is_empty() {
+        slides.push((&input[start..], start_line));
+    }
+    Ok(slides)
+}
+
+/// 組み立て中の表
+struct OpenTable {
+    alignments: Vec<Alignment>,
+    header: bool,
+    rows: Vec<Vec<TableCell>>,
+}
+
+/// 1枚分のイベント列を、スライドの中間表現に組み立てる
+struct SlideBuilder<'a> {
+    config: &'a DrawConfig,
+    text: &'a str,
+    first_line: usize,
+    slide: MarkdownSlide,
+    items: Vec<ListItem>,       // まだ FlowBlock にしていない段落とリスト項目
+    gap: bool,                  // 次の段落やリストの前に1行空けるか (直前が一番外側の段落・リスト)
+    inline: Vec<Content>,       // 組み立て中の1段落分
+    styles: Vec<FontStyle>,     // 入れ子になった **..** / *..* のスタイル
+    link: Option<Link>,         // 組み立て中のリンクの飛び先
+    size_ratio: f32,            // 組み立て中の段落の文字サイズ
+    lists: Vec<ListMarker>,     // 開いているリストの記号 (外側から順に)
+    marker: Option<ListMarker>, // まだ記号を付けていない項目の記号
+    skip: usize,                // 0より大きければ画像の代替テキストの中 (描かない)
+    code: Option<CodeBlock>,
+    table: Option<OpenTable>,
+    html: Option<(String, usize)>, // 組み立て中のHTMLブロックと、その始まりのバイト位置 (行ごとに届くのでまとめる)
+}
+
+impl<'a> SlideBuilder<'a> {
+    fn new(config: &'a DrawConfig, text: &'a str, first_line: usize) -> Self {
+        SlideBuilder {
+            config,
+            text,
+            first_line,
+            slide: MarkdownSlide {
+                title: None,
+                subtitle: None,
+                columns: vec![Vec::new()],
+                master: None,
+                layout: None,
+                column_breaks: Vec::new(),
+                notes: Vec::new(),
+            },
+            items: Vec::new(),
+            gap: false,
+            inline: Vec::new(),
+            styles: vec![config.default_font_style],
+            link: None,
+            size_ratio: config.body_size_ratio,
+            lists: Vec::new(),
+            marker: None,
+            skip: 0,
+            code: None,
+            table: None,
+            html: None,
+        }
+    }
+
+    fn build(mut self) -> Result<MarkdownSlide, ParseError> {
+        let options = Options::ENABLE_TABLES;
+        for (event, range) in Parser::new_ext(self.text, options).into_offset_iter() {
+            self.event(event, range)?;
+        }
+        self.flush_items();
+        Ok(self.slide)
+    }
+
+    fn event(&mut self, event: Event, range: Range<usize>) -> Result<(), ParseError> {
+        match event {
+            // --- ブロック ---
+            Event::Start(Tag::Heading { level, .. }) => {
+                self.flush_item();
+                let (style, size_ratio) = match level {
+                    HeadingLevel::H1 => (FontStyle::Bold, self.config.title_size_ratio),
+                    HeadingLevel::H2 => (self.config.default_font_style, self.config.subtitle_size_ratio),
+                    _ => (FontStyle::Bold, self.config.body_size_ratio),
+                };
+                self.start_block(style, size_ratio);
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                let contents = std::mem::take(&mut self.inline);
+                let slot = match level {
+                    HeadingLevel::H1 => Some((&mut self.slide.title, "title")),
+                    HeadingLevel::H2 => Some((&mut self.slide.subtitle, "subtitle")),
+                    _ => None,
+                };
+                match slot {
+                    Some((slot, _)) if slot.is_none() => *slot = Some(contents),
+                    Some((_, name)) => return Err(self.error(range.start, format!("slide already has a {}", name))),
+                    None => self.push_item(contents),
+                }
+                self.start_block(self.config.default_font_style, self.config.body_size_ratio);
+            }
+            Event::Start(Tag::Paragraph) => {}
+            Event::End(TagEnd::Paragraph) => self.flush_item(),
+            Event::Start(Tag::List(start)) => {
+                self.flush_item();
+                if self.lists.is_empty() {
+                    self.separate();
+                }
+                // "-" の記号は、slides.txt と同じく深さに応じて • – ・ を順に使う
+                let marker = match start {
+                    Some(_) => ListMarker::Decimal,
+                    None => [ListMarker::Bullet, ListMarker::Dash, ListMarker::Dot][self.lists.len() % 3],
+                };
+                self.lists.push(marker);
+            }
+            Event::End(TagEnd::List(_)) => {
+                self.lists.pop();
+                self.gap = self.lists.is_empty();
+            }
+            Event::Start(Tag::Item) => self.marker = self.lists.last().copied(),
+            Event::End(TagEnd::Item) => self.flush_item(),
+            Event::Start(Tag::CodeBlock(kind)) => {
+                self.flush_item();
+                self.flush_items();
+                let code = match kind {
+                    CodeBlockKind::Fenced(info) => parse_fence_info(&info).map_err(|word| {
+                        self.error(range.start, format!("unknown code block option '{}' (expected numbers or highlight=2-3,5)", word))
+                    })?,
+                    CodeBlockKind::Indented => CodeBlock::new(String::new(), CodeLanguage::Plain),
+                };
+                self.code = Some(code);
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some(mut code) = self.code.take() {
+                    code.source.truncate(code.source.trim_end_matches('\n').len());
+                    self.push_block(FlowBlock::Code(code));
+                }
+            }
+            Event::Start(Tag::Table(alignments)) => {
+                self.flush_item();
+                self.flush_items();
+                self.table = Some(OpenTable { alignments, header: false, rows: Vec::new() });
+            }
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Event::End(TagEnd::TableHead) => {
+                if let Some(table) = &mut self.table {
+                    table.header = true;
+                }
+            }
+            Event::Start(Tag::TableCell) => self.start_block(self.config.default_font_style, self.config.body_size_ratio),
+            Event::End(TagEnd::TableCell) => {
+                let contents = std::mem::take(&mut self.inline);
+                if let Some(table) = &mut self.table
+                    && let Some(row) = table.rows.last_mut()
+                {
+                    let mut cell = TableCell::new(contents);
+                    cell.h_align = match table.alignments.get(row.len()) {
+                        Some(Alignment::Left) => Some(HAlign::Left),
+                        Some(Alignment::Center) => Some(HAlign::Center),
+                        Some(Alignment::Right) => Some(HAlign::Right),
+                        _ => None,
+                    };
+                    row.push(cell);
+                }
+            }
+            Event::End(TagEnd::Table) => {
+                if let Some(table) = self.table.take() {
+                    self.push_block(FlowBlock::Table(Table::new(table.rows).with_header(table.header)));
+                }
+            }
+
+            // --- インライン ---
+            Event::Start(Tag::Strong) => self.styles.push(FontStyle::Bold),
+            // 太字の中の強調は、太字のまま (太字の斜体は無い)
+            Event::Start(Tag::Emphasis) => {
+                let style = if self.style() == FontStyle::Bold { FontStyle::Bold } else { FontStyle::Italic };
+                self.styles.push(style);
+            }
+            Event::End(TagEnd::Strong) | Event::End(TagEnd::Emphasis) => {
+                self.styles.pop();
+            }
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                let target = LinkTarget::parse(&dest_url)
+                    .ok_or_else(|| self.error(range.start, format!("invalid link '{}' (expected a URI or #slide-number)", dest_url)))?;
+                self.link = Some(Link { target, underline: self.config.link_underline });
+            }
+            Event::End(TagEnd::Link) => self.link = None,
+            Event::Start(Tag::Image { .. }) => self.skip += 1,
+            Event::End(TagEnd::Image) => self.skip -= 1,
+            Event::Text(text) => {
+                if self.code.is_none() && self.unclosed_comment(&text, &range) {
+                    return Err(self.error(range.start, "unterminated HTML comment in a paragraph (a blank line ends the paragraph; write the comment on its own lines)"));
+                }
+                match &mut self.code {
+                    Some(code) => code.source.push_str(&text),
+                    None => self.push_text(&text, self.style()),
+                }
+            }
+            Event::Code(text) => self.push_text(&text, FontStyle::Mono),
+            Event::SoftBreak => self.push_text(" ", self.style()),
+            Event::HardBreak => self.inline.push(Content::Newline),
+            Event::Start(Tag::HtmlBlock) => self.html = Some((String::new(), range.start)),
+            Event::End(TagEnd::HtmlBlock) => {
+                if let Some((html, start)) = self.html.take() {
+                    self.comment_directive(&html, start)?;
+                }
+            }
+            Event::Html(html) => match &mut self.html {
+                Some((block, _)) => block.push_str(&html),
+                None => self.comment_directive(&html, range.start)?,
+            },
+            Event::InlineHtml(html) => self.comment_directive(&html, range.start)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn style(&self) -> FontStyle {
+        self.styles.last().copied().unwrap_or(self.config.default_font_style)
+    }
+
+    /// 見出しや表のセルなど、段落とは別の文字サイズ・スタイルで組み立てを始める
+    fn start_block(&mut self, style: FontStyle, size_ratio: f32) {
+        self.styles = vec![style];
+        self.size_ratio = size_ratio;
+    }
+
+    fn push_text(&mut self, text: &str, style: FontStyle) {
+        if self.skip > 0 || text.is_empty() {
+            return;
+        }
+        self.inline.push(Content::Span(TextSpan {
+            text: text.to_string(),
+            style,
+            size_ratio: self.size_ratio,
+            color: match self.link {
+                Some(_) => self.config.link_color.unwrap_or(self.config.default_color),
+                None => self.config.default_color,
+            },
+            link: self.link.clone(),
+        }));
+    }
+
+    /// 組み立て中の段落を、段落またはリスト項目として確定させる。
+    /// 1つの項目の2つ目以降の段落は、記号の無い段落として項目のテキストの位置に揃える
+    fn flush_item(&mut self) {
+        if self.inline.is_empty() && self.marker.is_none() {
+            return;
+        }
+        let contents = std::mem::take(&mut self.inline);
+        self.push_item(contents);
+    }
+
+    fn push_item(&mut self, contents: Vec<Content>) {
+        let depth = self.lists.len();
+        if depth == 0 {
+            self.separate();
+            self.gap = true;
+        }
+        let item = match self.marker.take() {
+            Some(marker) => ListItem { level: depth - 1, marker: Some(marker), contents },
+            None => ListItem { level: depth, marker: None, contents },
+        };
+        self.items.push(item);
+    }
+
+    /// 一番外側の段落やリストどうしの間は1行空ける
+    fn separate(&mut self) {
+        if self.gap && !self.items.is_empty() {
+            self.items.push(ListItem { level: 0, marker: None, contents: Vec::new() });
+        }
+        self.gap = false;
+    }
+
+    /// 今の段の末尾にブロックを加える
+    fn push_block(&mut self, block: FlowBlock) {
+        if let Some(column) = self.slide.columns.last_mut() {
+            column.push(block);
+        }
+    }
+
+    /// ここまでの段落とリスト項目を1つのリストとして本文に加える
+    fn flush_items(&mut self) {
+        if !self.items.is_empty() {
+            let items = std::mem::take(&mut self.items);
+            self.push_block(FlowBlock::List(items));
+        }
+        self.gap = false;
+    }
+
+    /// "<!-- @名前 key=value ... -->" の指示を解釈する。それ以外のHTMLは無視する
+    fn comment_directive(&mut self, html: &str, offset: usize) -> Result<(), ParseError> {
+        let Some(inner) = html.trim().strip_prefix("<!--").and_then(|rest| rest.strip_suffix("-->")) else {
+            return Ok(());
+        };
+        let raw_line = inner.trim();
+        if let Some(notes) = raw_line.strip_prefix("notes:") {
+            self.push_notes(notes);
+            return Ok(());
+        }
+        if !raw_line.starts_with('@') {
+            return Ok(());
+        }
+        // 位置は "@" からの相対で返ってくるので、ファイル上の行・桁に直す
+        let (line, column) = self.position(offset + html.find('@').unwrap_or(0));
+        let shift = |mut error: ParseError| {
+            error.column += column - 1;
+            error
+        };
+        let mut directive = parse_directive(raw_line, line).map_err(shift)?;
+        directive.column = column;
+        for arg in &mut directive.args {
+            arg.column += column - 1;
+        }
+        let slot = match directive.name.as_str() {
+            "master" => &mut self.slide.master,
+            "layout" => &mut self.slide.layout,
+            "column" => {
+                self.flush_item();
+                self.flush_items();
+                self.slide.columns.push(Vec::new());
+                self.slide.column_breaks.push(directive);
+                return Ok(());
+            }
+            _ => return Err(directive.error(column + 1, format!("unknown directive '@{}'", directive.name))),
+        };
+        if slot.is_some() {
+            return Err(directive.error(column, format!("slide already has a @{}", directive.name)));
+        }
+        *slot = Some(directive);
+        Ok(())
+    }
+
+    /// ノートの文字を、行ごとに本文と同じスタイルのSpanにして加える (前後の空行は除く)
+    fn push_notes(&mut self, text: &str) {
+        for line in text.trim().lines() {
+            if !self.slide.notes.is_empty() {
+                self.slide.notes.push(Content::Newline);
+            }
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            self.slide.notes.push(Content::Span(TextSpan {
+                text: line.to_string(),
+                style: self.config.default_font_style,
+                size_ratio: self.config.body_size_ratio,
+                color: self.config.default_color,
+                link: None,
+            }));
+        }
+    }
+
+    /// 段落の中で閉じていない "<!--" か。閉じたコメントは InlineHtml で届くが、閉じる前に段落が
+    /// 終わると "<" だけの Text になる ("\<!--" と書いたときは "<" が後ろの文字とまとめて届く)
+    fn unclosed_comment(&self, text: &str, range: &Range<usize>) -> bool {
+        text == "<" && &self.text[range.clone()] == "<" && self.text[range.end..].starts_with("!--")
+    }
+
+    /// テキスト内のバイト位置を、ファイル上の (行, 桁) に直す
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let before = &self.text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |p| p + 1);
+        (self.first_line + before.matches('\n').count(), before[line_start..].chars().count() + 1)
+    }
+
+    /// テキスト内のバイト位置を指すエラーを作る
+    fn error(&self, offset: usize, message: impl Into<String>) -> ParseError {
+        let (line, column) = self.position(offset);
+        ParseError { line, column, message: message.into() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<Vec<MarkdownSlide>, ParseError> {
+        parse_markdown(input, &DrawConfig::from_grid(24.0, 32.0, 18.0))
+    }
+
+    fn error_at(result: Result<Vec<MarkdownSlide>, ParseError>) -> (usize, usize, String) {
+        let err = result.expect_err("expected a parse error");
+        (err.line, err.column, err.message)
+    }
+
+    /// 文字を繋げ、改行を "\n" にする
+    fn plain(contents: &[Content]) -> String {
+        contents
+            .iter()
+            .map(|content| match content {
+                Content::Span(span) => span.text.as_str(),
+                Content::Newline => "\n",
+            })
+            .collect()
+    }
+
+    /// 1段目の段落・リスト項目ごとの文字
+    fn body(slide: &MarkdownSlide) -> Vec<String> {
+        slide.columns[0]
+            .iter()
+            .flat_map(|block| match block {
+                FlowBlock::List(items) => items.iter().map(|item| plain(&item.contents)).collect(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn separators_inside_code_fences_do_not_split() {
+        let input = "a\n---\n```\n---\n```\nb\n---\n~~~~\n---\n~~~\n---\n~~~~\n";
+        let slides = split_slides(input).unwrap();
+        assert_eq!(slides, [("a\n", 1), ("```\n---\n```\nb\n", 3), ("~~~~\n---\n~~~\n---\n~~~~\n", 8)]);
+        // 4文字以上字下げした "```" はフェンスではない
+        assert_eq!(split_slides("    ```\n---\nb\n").unwrap(), [("    ```\n", 1), ("b\n", 3)]);
+        // 最後の区切りの後が空なら空のスライドは作らない
+        assert_eq!(split_slides("a\n---\n\n").unwrap(), [("a\n", 1)]);
+        assert_eq!(split_slides("").unwrap(), [("", 1)]);
+    }
+
+    #[test]
+    fn unterminated_fence_points_at_its_opening_line() {
+        assert_eq!(
+            error_at(parse("a\n---\n````\ncode\n```\n")),
+            (3, 1, "unterminated code block (missing closing ````)".to_string())
+        );
+    }
+
+    #[test]
+    fn directives_report_file_positions() {
+        let slides = parse("# A\n---\n# B\n<!-- @layout name=two-column -->\n").unwrap();
+        let layout = slides[1].layout.as_ref().unwrap();
+        assert_eq!((layout.line, layout.column, layout.args[0].column), (4, 6, 14));
+
+        assert_eq!(
+            error_at(parse("# A\n---\ntext\n\n<!-- @layout name=x bad -->\n")),
+            (5, 21, "expected 'key=value' but found 'bad'".to_string())
+        );
+        assert_eq!(error_at(parse("x\n---\ntext <!-- @foo -->\n")), (3, 12, "unknown directive '@foo'".to_string()));
+        assert_eq!(error_at(parse("# A\n# B\n")), (2, 1, "slide already has a title".to_string()));
+    }
+
+    #[test]
+    fn column_directive_starts_a_new_column() {
+        let slides = parse("left\n\n<!-- @column -->\n\nright\n").unwrap();
+        let columns: Vec<usize> = slides[0].columns.iter().map(Vec::len).collect();
+        assert_eq!(columns, [1, 1]);
+        assert_eq!(slides[0].column_breaks[0].line, 3);
+    }
+
+    #[test]
+    fn notes_comments_span_lines() {
+        let slides = parse("# A\n<!-- notes: one line -->\n---\ntext\n\n<!-- notes: first\n  second\n\n  third -->\n").unwrap();
+        assert_eq!(plain(&slides[0].notes), "one line");
+        assert_eq!(plain(&slides[1].notes), "first\nsecond\n\nthird");
+        assert_eq!(body(&slides[1]), ["text"]);
+    }
+
+    #[test]
+    fn notes_comment_inside_a_paragraph() {
+        let slides = parse("body <!-- notes:\n  a **b**\n  c --> tail\n").unwrap();
+        assert_eq!(plain(&slides[0].notes), "a **b**\nc");
+        assert_eq!(body(&slides[0]), ["body  tail"]);
+    }
+
+    #[test]
+    fn comment_cut_by_a_blank_line_is_rejected() {
+        assert_eq!(
+            error_at(parse("x\n---\nbody <!-- notes:\na\n\nb -->\n")),
+            (3, 6, "unterminated HTML comment in a paragraph (a blank line ends the paragraph; write the comment on its own lines)".to_string())
+        );
+        assert!(parse("- item <!-- notes: a\n\n  b -->\n").is_err());
+        // "\<!--" はただの文字
+        let slides = parse("a \\<!-- b\n").unwrap();
+        assert_eq!(body(&slides[0]), ["a <!-- b"]);
+        assert!(slides[0].notes.is_empty());
+    }
+}
@@ -0,0 +1,293 @@
+// --- インライン・マークアップのパーサー ---
+//
+// 書式:
+//   **太字**                 : FontStyle::Bold
+//   {size=2}大きく{/size}     : size_ratio を指定値に変更
+//   {color=red}赤{/color}     : 色名 (black/white/red/green/blue とテーマの色名) または #rrggbb
+//   {link=https://...}文字{/link} : リンク ("#3" なら3枚目のスライドへ。色と下線はテーマの [links] に従う)
+//   \*  \{  \\               : 直後の1文字をそのまま出力する
+//
+// タグは入れ子にでき、内側のタグは外側のスタイルを引き継ぐ。
+
+use crate::{Content, DrawConfig, FontStyle, Link, LinkTarget, SlideColor, TextSpan};
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct MarkupError {
+    pub column: usize, // 入力テキスト内の1始まりの桁位置 (文字単位)
+    pub message: String,
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for MarkupError {}
+
+/// 解釈中の「現在のスタイル」
+#[derive(Clone)]
+struct SpanStyle {
+    style: FontStyle,
+    size_ratio: f32,
+    color: SlideColor,
+    link: Option<Link>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tag {
+    Bold,
+    Size,
+    Color,
+    Link,
+}
+
+impl Tag {
+    fn closing(self) -> &'static str {
+        match self {
+            Tag::Bold => "**",
+            Tag::Size => "{/size}",
+            Tag::Color => "{/color}",
+            Tag::Link => "{/link}",
+        }
+    }
+}
+
+/// 開いているタグと、そのタグを開く直前のスタイル
+struct OpenTag {
+    tag: Tag,
+    column: usize,
+    outer: SpanStyle,
+}
+
+/// 1行分のマークアップを解釈し、Spanのリストに変換する。
+/// 色は `config.default_color` を、フォントスタイルは `base_style` を起点に引き継ぐ。
+pub fn parse_inline(
+    text: &str,
+    config: &DrawConfig,
+    base_style: FontStyle,
+    base_size_ratio: f32,
+) -> Result<Vec<Content>, MarkupError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut contents: Vec<Content> = Vec::new();
+    let mut stack: Vec<OpenTag> = Vec::new();
+    let mut current = SpanStyle { style: base_style, size_ratio: base_size_ratio, color: config.default_color, link: None };
+    let mut buffer = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let column = i + 1;
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                buffer.push(chars[i + 1]);
+                i += 2;
+            }
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                flush(&mut contents, &mut buffer, &current);
+                if stack.last().is_some_and(|open| open.tag == Tag::Bold) {
+                    if let Some(open) = stack.pop() {
+                        current = open.outer;
+                    }
+                } else if let Some(open) = stack.iter().find(|open| open.tag == Tag::Bold) {
+                    return Err(MarkupError {
+                        column,
+                        message: format!("'**' closes bold opened at column {} before inner tags are closed", open.column),
+                    });
+                } else {
+                    stack.push(OpenTag { tag: Tag::Bold, column, outer: current.clone() });
+                    current.style = FontStyle::Bold;
+                }
+                i += 2;
+            }
+            '{' => {
+                let close = chars[i..].iter().position(|&c| c == '}').map(|p| i + p).ok_or_else(|| MarkupError {
+                    column,
+                    message: "unterminated '{'".to_string(),
+                })?;
+                let tag_text: String = chars[i + 1..close].iter().collect();
+                flush(&mut contents, &mut buffer, &current);
+
+                if let Some(name) = tag_text.strip_prefix('/') {
+                    let tag = match name {
+                        "size" => Tag::Size,
+                        "color" => Tag::Color,
+                        "link" => Tag::Link,
+                        _ => return Err(MarkupError { column, message: format!("unknown closing tag '{{{}}}'", tag_text) }),
+                    };
+                    match stack.pop() {
+                        Some(open) if open.tag == tag => current = open.outer,
+                        Some(open) => {
+                            return Err(MarkupError {
+                                column,
+                                message: format!("expected '{}' but found '{{{}}}'", open.tag.closing(), tag_text),
+                            });
+                        }
+                        None => return Err(MarkupError { column, message: format!("'{{{}}}' has no opening tag", tag_text) }),
+                    }
+                } else {
+                    let (key, value) = tag_text.split_once('=').ok_or_else(|| MarkupError {
+                        column,
+                        message: format!("expected '{{key=value}}' but found '{{{}}}'", tag_text),
+                    })?;
+                    let (key, value) = (key.trim(), value.trim());
+                    let outer = current.clone();
+                    let tag = match key {
+                        "size" => {
+                            current.size_ratio = value
+                                .parse::<f32>()
+                                .ok()
+                                .filter(|v| v.is_finite() && *v > 0.0)
+                                .ok_or_else(|| MarkupError { column, message: format!("invalid size '{}'", value) })?;
+                            Tag::Size
+                        }
+                        "color" => {
+                            current.color = SlideColor::parse(value, &config.palette)
+                                .ok_or_else(|| MarkupError { column, message: format!("unknown color '{}'", value) })?;
+                            Tag::Color
+                        }
+                        "link" => {
+                            let target = LinkTarget::parse(value).ok_or_else(|| MarkupError {
+                                column,
+                                message: format!("invalid link '{}' (expected a URI or #slide-number)", value),
+                            })?;
+                            if let Some(color) = config.link_color {
+                                current.color = color;
+                            }
+                            current.link = Some(Link { target, underline: config.link_underline });
+                            Tag::Link
+                        }
+                        _ => return Err(MarkupError { column, message: format!("unknown tag '{}'", key) }),
+                    };
+                    stack.push(OpenTag { tag, column, outer });
+                }
+                i = close + 1;
+            }
+            c => {
+                buffer.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    if let Some(open) = stack.last() {
+        return Err(MarkupError { column: open.column, message: format!("missing closing '{}'", open.tag.closing()) });
+    }
+    flush(&mut contents, &mut buffer, &current);
+    Ok(contents)
+}
+
+/// バッファに溜まった文字列を、現在のスタイルのSpanとして確定させる
+fn flush(contents: &mut Vec<Content>, buffer: &mut String, style: &SpanStyle) {
+    if buffer.is_empty() {
+        return;
+    }
+    contents.push(Content::Span(TextSpan {
+        text: std::mem::take(buffer),
+        style: style.style,
+        size_ratio: style.size_ratio,
+        color: style.color,
+        link: style.link.clone(),
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NamedColor;
+
+    fn parse(text: &str) -> Result<Vec<TextSpan>, MarkupError> {
+        let config = DrawConfig::from_grid(24.0, 32.0, 18.0);
+        let contents = parse_inline(text, &config, FontStyle::Regular, 1.0)?;
+        Ok(contents
+            .into_iter()
+            .map(|content| match content {
+                Content::Span(span) => span,
+                Content::Newline => panic!("parse_inline never emits newlines"),
+            })
+            .collect())
+    }
+
+    fn error(text: &str) -> (usize, String) {
+        let err = parse(text).expect_err(text);
+        (err.column, err.message)
+    }
+
+    #[test]
+    fn plain_text_is_one_span() {
+        let spans = parse("こんにちは world").unwrap();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].text, "こんにちは world");
+        assert_eq!(spans[0].style, FontStyle::Regular);
+        assert!(parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn nested_tags_inherit_outer_style() {
+        let spans = parse("a{color=red}b**c{size=2}d{/size}**e{/color}f").unwrap();
+        let texts: Vec<&str> = spans.iter().map(|span| span.text.as_str()).collect();
+        assert_eq!(texts, ["a", "b", "c", "d", "e", "f"]);
+
+        let d = &spans[3];
+        assert_eq!(d.style, FontStyle::Bold);
+        assert_eq!(d.size_ratio, 2.0);
+        assert!(matches!(d.color, SlideColor::Named(NamedColor::Red)));
+        // 閉じたタグの外側は、開く前のスタイルに戻る
+        assert_eq!(spans[4].style, FontStyle::Regular);
+        assert!(matches!(spans[4].color, SlideColor::Named(NamedColor::Red)));
+        assert!(matches!(spans[5].color, SlideColor::Named(NamedColor::Black)));
+    }
+
+    #[test]
+    fn custom_colors_and_links() {
+        let spans = parse("{color=#ff8000}x{/color}{link=#3}y{/link}{link=https://example.com}z{/link}").unwrap();
+        assert!(matches!(spans[0].color, SlideColor::Custom(r, _, b) if r == 1.0 && b == 0.0));
+        assert_eq!(spans[1].link.as_ref().map(|link| &link.target), Some(&LinkTarget::Slide(3)));
+        assert_eq!(spans[2].link.as_ref().map(|link| &link.target), Some(&LinkTarget::Uri("https://example.com".to_string())));
+        assert!(spans[2].link.as_ref().is_some_and(|link| link.underline));
+    }
+
+    #[test]
+    fn escapes_output_the_next_character() {
+        let spans = parse(r"\*\*x\{y\}\\z").unwrap();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].text, r"**x{y}\z");
+        // 行末の "\" はそのまま残る
+        assert_eq!(parse(r"a\").unwrap()[0].text, r"a\");
+    }
+
+    #[test]
+    fn mismatched_tags_report_the_closing_column() {
+        assert_eq!(error("{color=red}**x{/color}**"), (15, "expected '**' but found '{/color}'".to_string()));
+        assert_eq!(
+            error("**a{size=2}b**"),
+            (13, "'**' closes bold opened at column 1 before inner tags are closed".to_string())
+        );
+        assert_eq!(error("{size=2}**ab{/size}"), (13, "expected '**' but found '{/size}'".to_string()));
+        assert_eq!(error("ab{/link}"), (3, "'{/link}' has no opening tag".to_string()));
+        assert_eq!(error("{/bold}"), (1, "unknown closing tag '{/bold}'".to_string()));
+    }
+
+    #[test]
+    fn unclosed_tags_report_the_opening_column() {
+        assert_eq!(error("x{color=red}abc"), (2, "missing closing '{/color}'".to_string()));
+        assert_eq!(error("ab**"), (3, "missing closing '**'".to_string()));
+        assert_eq!(error("ab{color"), (3, "unterminated '{'".to_string()));
+    }
+
+    #[test]
+    fn columns_count_characters_not_bytes() {
+        assert_eq!(error("日本語{/size}"), (4, "'{/size}' has no opening tag".to_string()));
+    }
+
+    #[test]
+    fn invalid_tag_values() {
+        assert_eq!(error("{size=0}a{/size}"), (1, "invalid size '0'".to_string()));
+        assert_eq!(error("{size=inf}a{/size}"), (1, "invalid size 'inf'".to_string()));
+        assert_eq!(error("a{size=NaN}"), (2, "invalid size 'NaN'".to_string()));
+        assert_eq!(error("a{color=mauve}"), (2, "unknown color 'mauve'".to_string()));
+        assert_eq!(error("{link=#0}a{/link}"), (1, "invalid link '#0' (expected a URI or #slide-number)".to_string()));
+        assert_eq!(error("{font=mono}"), (1, "unknown tag 'font'".to_string()));
+        assert_eq!(error("{bold}"), (1, "expected '{key=value}' but found '{bold}'".to_string()));
+    }
+}
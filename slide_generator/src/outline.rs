@@ -0,0 +1,81 @@
+// --- PDFのしおり (アウトライン) ---
+//
+// printpdf のしおりは入れ子にできないので、保存したPDFを lopdf で読み直してしおりを付け足す。
+// 章の扉のスライドは一番上の段に置き、次の章の扉までのスライドをその下にまとめる。
+// 読み直したついでに、printpdf が置き場所を間違えるリンクの注釈もページに移す。
+
+use crate::error::SlideError;
+use lopdf::{Bookmark, Document, Object};
+
+/// しおり1つ分。`page` は0始まりのページ番号。
+/// タイトルの無い章の扉はしおりを作らず、後に続くスライドを一番上の段に戻す
+pub(crate) struct OutlineEntry {
+    pub title: Option<String>,
+    pub page: usize,
+    pub section: bool,
+}
+
+/// 保存した `pdf` を読み直し、リンクの注釈をページに移してしおりを付ける
+pub(crate) fn finish_pdf(pdf: &[u8], entries: &[OutlineEntry]) -> Result<Vec<u8>, SlideError> {
+    let mut doc = Document::load_mem(pdf).map_err(post_process_error)?;
+    move_link_annotations(&mut doc);
+    add_outline(&mut doc, entries)?;
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes).map_err(|e| SlideError::PostProcess(e.to_string()))?; // 書き込みのエラーは io::Error
+    Ok(bytes)
+}
+
+fn post_process_error(e: lopdf::Error) -> SlideError {
+    SlideError::PostProcess(e.to_string())
+}
+
+/// printpdf 0.8 はリンクの注釈 (/Annots) をページの /Resources の中に直接書くため、ビューアーがリンクを見つけられない。
+/// 注釈を1つずつ間接オブジェクトにして、ページの辞書から参照する
+fn move_link_annotations(doc: &mut Document) {
+    for page in doc.get_pages().into_values() {
+        let Ok(resources) = doc.get_dictionary(page).and_then(|page| page.get(b"Resources")).and_then(Object::as_reference) else {
+            continue;
+        };
+        let annots = doc.get_dictionary_mut(resources).ok().and_then(|resources| resources.remove(b"Annots"));
+        let Some(Object::Array(annots)) = annots else {
+            continue;
+        };
+        if annots.is_empty() {
+            continue;
+        }
+        let refs: Vec<Object> = annots.into_iter().map(|annot| Object::Reference(doc.add_object(annot))).collect();
+        if let Ok(page) = doc.get_dictionary_mut(page) {
+            page.set("Annots", Object::Array(refs));
+        }
+    }
+}
+
+/// しおりを付け、PDFを開いたときにしおりの欄を表示するようにする。タイトルのあるしおりが無ければ何もしない
+fn add_outline(doc: &mut Document, entries: &[OutlineEntry]) -> Result<(), SlideError> {
+    if entries.iter().all(|entry| entry.title.is_none()) {
+        return Ok(());
+    }
+    let pages = doc.get_pages(); // 1始まりのページ番号 → ページのオブジェクト
+
+    let mut section: Option<u32> = None;
+    for entry in entries {
+        let Some(title) = &entry.title else {
+            section = None;
+            continue;
+        };
+        let page = *pages.get(&(entry.page as u32 + 1)).ok_or_else(|| SlideError::PostProcess(format!("no page {} for outline", entry.page + 1)))?;
+        if entry.section {
+            // 章の扉は太字にして、後に続くスライドの親にする
+            section = Some(doc.add_bookmark(Bookmark::new(title.clone(), [0.0; 3], 2, page), None));
+        } else {
+            doc.add_bookmark(Bookmark::new(title.clone(), [0.0; 3], 0, page), section);
+        }
+    }
+
+    if let Some(outline) = doc.build_outline() {
+        let catalog = doc.catalog_mut().map_err(post_process_error)?;
+        catalog.set("Outlines", Object::Reference(outline));
+        catalog.set("PageMode", Object::Name(b"UseOutlines".to_vec()));
+    }
+    Ok(())
+}
@@ -0,0 +1,108 @@
+// --- レイアウト済みのページ ---
+//
+// スライドをレイアウトした結果を、出力形式によらない描画命令の列として表す。
+// 座標と大きさはpt単位で、原点はページの左下 (PDFと同じ向き)。
+// PDF や SVG などの出力は Renderer を実装し、この命令の列をそれぞれの形式に変換する。
+
+use crate::error::SlideError;
+use crate::{FontStyle, ImageFit, LinkTarget, SlideColor};
+use std::path::PathBuf;
+
+/// パスの点。bezier = true の点は曲線の制御点 (2つ続けて置き、その次の点が曲線の終点になる)
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PathPoint {
+    pub x: f32,
+    pub y: f32,
+    pub bezier: bool,
+}
+
+/// 左上 (x, y_top) から幅 width ・高さ height の範囲
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Frame {
+    pub x: f32,
+    pub y_top: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// 線の描き方。dash は線と隙間の長さ (pt) を交互に並べたもので、空なら実線
+#[derive(Debug, Clone)]
+pub(crate) struct Stroke {
+    pub color: SlideColor,
+    pub width: f32,
+    pub dash: Vec<u32>,
+}
+
+/// 描画命令。後の命令ほど手前に描かれる
+#[derive(Debug, Clone)]
+pub(crate) enum DrawOp {
+    /// 1つのSpan。(x, baseline) はベースラインの左端、letter_spacing は文字ごとに足す間隔
+    Text { x: f32, baseline: f32, style: FontStyle, size: f32, color: SlideColor, letter_spacing: f32, text: String },
+    /// 点をつないだ線。closed なら閉じた図形として fill で塗り、stroke で縁取る
+    Path { points: Vec<PathPoint>, closed: bool, fill: Option<SlideColor>, stroke: Option<Stroke> },
+    /// 画像ファイルを frame に fit で収める (はみ出した部分は描かない)
+    Image { path: PathBuf, frame: Frame, fit: ImageFit },
+    /// 次の PopClip までの命令を、frame の内側だけに描く
+    PushClip(Frame),
+    PopClip,
+    /// frame の範囲をクリックすると target に飛ぶ
+    Link { frame: Frame, target: LinkTarget },
+}
+
+impl Frame {
+    fn scaled(self, scale: f32, dx: f32, dy: f32) -> Frame {
+        Frame { x: self.x * scale + dx, y_top: self.y_top * scale + dy, width: self.width * scale, height: self.height * scale }
+    }
+}
+
+impl DrawOp {
+    /// 座標と大きさを `scale` 倍し、(dx, dy) だけずらした命令 (ページを縮小して別のページに置くときに使う)
+    pub(crate) fn scaled(&self, scale: f32, dx: f32, dy: f32) -> DrawOp {
+        match self {
+            DrawOp::Text { x, baseline, style, size, color, letter_spacing, text } => DrawOp::Text {
+                x: x * scale + dx,
+                baseline: baseline * scale + dy,
+                style: *style,
+                size: size * scale,
+                color: *color,
+                letter_spacing: letter_spacing * scale,
+                text: text.clone(),
+            },
+            DrawOp::Path { points, closed, fill, stroke } => DrawOp::Path {
+                points: points.iter().map(|p| PathPoint { x: p.x * scale + dx, y: p.y * scale + dy, bezier: p.bezier }).collect(),
+                closed: *closed,
+                fill: *fill,
+                stroke: stroke.as_ref().map(|stroke| Stroke {
+                    color: stroke.color,
+                    width: stroke.width * scale,
+                    // 破線の長さは整数なので、縮めても1pt未満にはしない
+                    dash: stroke.dash.iter().map(|d| ((*d as f32 * scale).round() as u32).max(1)).collect(),
+                }),
+            },
+            DrawOp::Image { path, frame, fit } => DrawOp::Image { path: path.clone(), frame: frame.scaled(scale, dx, dy), fit: *fit },
+            DrawOp::PushClip(frame) => DrawOp::PushClip(frame.scaled(scale, dx, dy)),
+            DrawOp::PopClip => DrawOp::PopClip,
+            DrawOp::Link { frame, target } => DrawOp::Link { frame: frame.scaled(scale, dx, dy), target: target.clone() },
+        }
+    }
+}
+
+/// 1ページ分の描画命令
+#[derive(Debug, Clone)]
+pub(crate) struct Page {
+    pub width: f32,
+    pub height: f32,
+    pub ops: Vec<DrawOp>,
+}
+
+/// レイアウト済みのページを、出力形式に変換する
+pub(crate) trait Renderer {
+    /// ページを先頭から順に1枚ずつ受け取る
+    fn render_page(&mut self, page: &Page) -> Result<(), SlideError>;
+}
+
+/// `page` 枚目 (1始まり) のページのファイル名 ("slide-01.svg" など)。桁数は総ページ数 `pages` に合わせる (最低2桁)
+pub(crate) fn page_file_name(page: usize, pages: usize, extension: &str) -> String {
+    let digits = pages.to_string().len().max(2);
+    format!("slide-{:0digits$}.{}", page, extension)
+}
@@ -0,0 +1,921 @@
+// --- スライドファイル (slides.txt) のパーサー ---
+//
+// 書式:
+//   ---          : この行だけでスライドを区切る
+//   # タイトル   : スライドのタイトル (1スライドにつき1つ、本文より前に書く。PDFのしおりの見出しにもなる)
+//   ## サブタイトル : タイトルに添える小さめの見出し (タイトルと同じく1つだけ、本文より前に書く)
+//   // コメント  : 無視される行
+//   @名前 key=value ... : スライドへの指示 (値に空白を含めるときは key="..." と書く)
+//       @background color=色 image=画像 fit=cover|contain|stretch : このスライドだけ背景を変える
+//       @master name=マスター名|none : このスライドに使うマスター (テーマで定義したもの。none ならフッターなどを描かない)
+//       @layout name=title|title-body|two-column|section : タイトルと本文の配置 (省略すると title-body)
+//                    section のスライドは章の扉になり、次の章の扉までのスライドのしおりをその下にまとめる
+//       @column      : ここから先の本文を次の段に流す (two-column の右の段など)
+//       @notes       : この行から次の --- までをスピーカーノートにする (スライドには描かず、ノートのページに載せる)
+//                    ノートの各行にもインライン・マークアップが使える (// のコメント行は無視する)
+//       @image src=画像 col=列 row=行 width=幅 height=高さ fit=contain|cover|stretch
+//                    : 画像を置く (単位はグリッド、fitは省略するとcontain)
+//       @rect col= row= width= height= radius=角の半径      : 長方形 (radiusを付けると角丸)
+//       @ellipse col= row= width= height=                  : 楕円
+//       @line points="列,行 列,行 ..." heads=none|start|end|both : 線・折れ線・矢印 (@arrow は heads=end が既定)
+//                    図形の共通キー: fill=色 stroke=色|none stroke_width=太さ(pt) dash=線,隙間,...(pt)
+//       画像と図形は書かれた順に重なる。layer=back (既定) ならテキストより奥、layer=front なら手前に描かれる
+//   | a | b |    : 表の1行 (2行目を |---|:--:|--:| にすると1行目が見出しになり、":" で列の揃え方を指定できる)
+//       @table widths=6,auto padding=余白 border=色|none border_width=太さ(pt) header_fill=色|none stripe=色|none
+//                    : 直後の表の見た目を変える (widthsの数値はグリッド単位の列幅、autoは中身に合わせる)
+//   ```言語 numbers highlight=2-3,5
+//   ...
+//   ```          : コードブロック (言語は rust/python/sh/json。numbers で行番号、highlight で行を強調)
+//   - 項目       : 箇条書き (行頭の空白2つで1段深くなる。"・" "•" "–" で記号を、"1." "a)" "①" "(i)" で番号の種類を指定できる)
+//                    番号付きの項目は最初の番号から始めるか、直前の行が項目のときだけ項目になる ("(c) 2024" は文のまま)
+//                    行頭の記号を文字として書くときは "\1." "\-" のように "\" を前に付ける
+//   それ以外     : 本文の1行 (空行もそのまま空行として扱う。先頭の @ は \@ と書く)
+//
+// タイトルと本文の各行には、行ごとにインライン・マークアップ (markup.rs) が使える。
+
+use crate::image::ImageLayout;
+use crate::layout::SlideLayout;
+use crate::markup;
+use crate::{
+    ArrowHeads, Background, BackgroundImage, ColumnWidth, Content, DrawConfig, FontStyle, HAlign, ImageFit, Shape, SlideColor, Table,
+    TableCell,
+};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// ファイル上の位置を保持したテキスト1行分
+#[derive(Debug, Clone)]
+pub struct SourceLine {
+    pub text: String,
+    pub line: usize,   // 1始まりの行番号
+    pub column: usize, // textが始まる、1始まりの桁位置 (文字単位)
+}
+
+impl SourceLine {
+    /// マークアップを解釈してSpanのリストに変換する。エラー位置はファイル上の行・桁に直して返す。
+    pub fn to_contents(&self, config: &DrawConfig, base_style: FontStyle, base_size_ratio: f32) -> Result<Vec<Content>, ParseError> {
+        markup::parse_inline(&self.text, config, base_style, base_size_ratio).map_err(|e| ParseError {
+            line: self.line,
+            column: self.column + e.column - 1,
+            message: e.message,
+        })
+    }
+}
+
+/// "@名前 key=value ..." 形式の指示行
+#[derive(Debug, Clone)]
+pub struct Directive {
+    pub name: String,
+    pub line: usize,
+    pub column: usize, // "@" の桁位置
+    pub args: Vec<DirectiveArg>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DirectiveArg {
+    pub key: String,
+    pub value: String,
+    pub column: usize, // key の桁位置
+}
+
+impl Directive {
+    /// 指示行内の位置を指すエラーを作る
+    pub fn error(&self, column: usize, message: impl Into<String>) -> ParseError {
+        ParseError { line: self.line, column, message: message.into() }
+    }
+
+    /// `allowed` に無いキーが指定されていたらエラーにする
+    pub fn check_keys(&self, allowed: &[&str]) -> Result<(), ParseError> {
+        match self.args.iter().find(|arg| !allowed.contains(&arg.key.as_str())) {
+            Some(arg) => Err(self.error(arg.column, format!("unknown key '{}' for @{} (expected one of: {})", arg.key, self.name, allowed.join(", ")))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&DirectiveArg> {
+        self.args.iter().find(|arg| arg.key == key)
+    }
+
+    /// 必須のキーを取り出す
+    pub fn require(&self, key: &str) -> Result<&DirectiveArg, ParseError> {
+        self.get(key).ok_or_else(|| self.error(self.column, format!("@{} needs '{}'", self.name, key)))
+    }
+
+    /// 数値のキーを取り出す (指定されていなければNone)
+    pub fn number(&self, key: &str) -> Result<Option<f32>, ParseError> {
+        let Some(arg) = self.get(key) else { return Ok(None) };
+        match arg.value.parse::<f32>() {
+            Ok(v) if v.is_finite() => Ok(Some(v)),
+            _ => Err(self.error(arg.column, format!("invalid {} '{}'", key, arg.value))),
+        }
+    }
+}
+
+/// パース結果としてのスライド1枚分
+#[derive(Debug, Clone, Default)]
+pub struct SlideSource {
+    pub title: Option<SourceLine>,
+    pub subtitle: Option<SourceLine>,
+    pub body: Vec<SourceLine>,
+    pub background: Option<Directive>,
+    pub master: Option<Directive>,
+    pub layout: Option<Directive>,
+    pub elements: Vec<Directive>,      // @image などの配置指示 (書かれた順)
+    pub tables: Vec<Directive>,        // @table (それぞれ、後に続く表に使う)
+    pub column_breaks: Vec<Directive>, // @column (本文のこの位置で次の段に移る)
+    pub notes: Vec<SourceLine>,        // @notes より後の行 (スピーカーノート)
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+const SLIDE_SEPARATOR: &str = "---";
+const TITLE_PREFIX: &str = "#";
+const SUBTITLE_PREFIX: &str = "##";
+const COMMENT_PREFIX: &str = "//";
+const DIRECTIVE_PREFIX: char = '@';
+pub(crate) const CODE_FENCE: &str = "```";
+
+/// スライドファイル全体を読み取り、スライドのリストに変換する
+pub fn parse_deck(input: &str) -> Result<Vec<SlideSource>, ParseError> {
+    let mut slides: Vec<SlideSource> = Vec::new();
+    let mut current = SlideSource::default();
+    let mut open_fence: Option<usize> = None; // 閉じていない "```" の行番号
+    let mut in_notes = false; // @notes より後 (次の区切りまでノートとして読む)
+
+    for (index, raw_line) in input.lines().enumerate() {
+        let line_no = index + 1;
+
+        // ノートの中は区切りとコメントだけを見る (見出しや指示としては扱わない)
+        if in_notes {
+            if raw_line.trim_end() == SLIDE_SEPARATOR {
+                slides.push(finish_slide(current));
+                current = SlideSource::default();
+                in_notes = false;
+            } else if !raw_line.starts_with(COMMENT_PREFIX) {
+                current.notes.push(SourceLine { text: raw_line.trim_end().to_string(), line: line_no, column: 1 });
+            }
+            continue;
+        }
+
+        // コードブロックの中はそのまま本文に入れる (区切りやコメントとしては扱わない)
+        if open_fence.is_some() {
+            if raw_line.trim_end() == CODE_FENCE {
+                open_fence = None;
+            }
+            current.body.push(SourceLine { text: raw_line.trim_end().to_string(), line: line_no, column: 1 });
+            continue;
+        }
+        if raw_line.starts_with(CODE_FENCE) {
+            open_fence = Some(line_no);
+            current.body.push(SourceLine { text: raw_line.trim_end().to_string(), line: line_no, column: 1 });
+            continue;
+        }
+
+        if raw_line.trim_end() == SLIDE_SEPARATOR {
+            slides.push(finish_slide(current));
+            current = SlideSource::default();
+            continue;
+        }
+        if raw_line.starts_with(COMMENT_PREFIX) {
+            continue;
+        }
+
+        if raw_line.starts_with(DIRECTIVE_PREFIX) {
+            let directive = parse_directive(raw_line, line_no)?;
+            match directive.name.as_str() {
+                "background" => {
+                    if current.background.is_some() {
+                        return Err(directive.error(1, "slide already has a @background"));
+                    }
+                    current.background = Some(directive);
+                }
+                "master" => {
+                    if current.master.is_some() {
+                        return Err(directive.error(1, "slide already has a @master"));
+                    }
+                    current.master = Some(directive);
+                }
+                "layout" => {
+                    if current.layout.is_some() {
+                        return Err(directive.error(1, "slide already has a @layout"));
+                    }
+                    current.layout = Some(directive);
+                }
+                "column" => current.column_breaks.push(directive),
+                "notes" => {
+                    if let Some(arg) = directive.args.first() {
+                        return Err(directive.error(arg.column, "@notes takes no keys"));
+                    }
+                    in_notes = true;
+                }
+                "image" | "rect" | "ellipse" | "line" | "arrow" => current.elements.push(directive),
+                "table" => current.tables.push(directive),
+                _ => return Err(directive.error(2, format!("unknown directive '@{}'", directive.name))),
+            }
+            continue;
+        }
+
+        // "##" を先に調べる (サブタイトルの行は "#" でも始まるため)
+        let heading = match raw_line.strip_prefix(SUBTITLE_PREFIX) {
+            Some(rest) => Some((rest, SUBTITLE_PREFIX, "subtitle")),
+            None => raw_line.strip_prefix(TITLE_PREFIX).map(|rest| (rest, TITLE_PREFIX, "title")),
+        };
+        if let Some((rest, prefix, name)) = heading {
+            let slot = if name == "title" { &mut current.title } else { &mut current.subtitle };
+            if slot.is_some() {
+                return Err(ParseError { line: line_no, column: 1, message: format!("slide already has a {}", name) });
+            }
+            if current.body.iter().any(|l| !l.text.is_empty()) {
+                return Err(ParseError { line: line_no, column: 1, message: format!("{} must come before body text", name) });
+            }
+            // "#" の直後の空白は区切りとして読み飛ばす
+            let text = rest.trim_start();
+            let column = 1 + prefix.len() + (rest.chars().count() - text.chars().count());
+            let text = text.trim_end();
+            if text.is_empty() {
+                return Err(ParseError { line: line_no, column, message: format!("empty {}", name) });
+            }
+            *slot = Some(SourceLine { text: text.to_string(), line: line_no, column });
+            current.body.clear(); // タイトル前の空行は捨てる
+            continue;
+        }
+
+        current.body.push(SourceLine { text: raw_line.trim_end().to_string(), line: line_no, column: 1 });
+    }
+
+    if let Some(line) = open_fence {
+        return Err(ParseError { line, column: 1, message: "unterminated code block (missing closing ```)".to_string() });
+    }
+    // 最後の区切り以降に何も書かれていなければ、空のスライドは作らない
+    if current.title.is_some() || current.body.iter().any(|l| !l.text.is_empty()) || current.background.is_some()
+        || current.subtitle.is_some()
+        || current.master.is_some()
+        || current.layout.is_some()
+        || !current.elements.is_empty()
+        || !current.notes.is_empty()
+        || slides.is_empty() {
+        slides.push(finish_slide(current));
+    }
+    Ok(slides)
+}
+
+/// 本文とノートの先頭と末尾の空行を取り除く
+fn finish_slide(mut slide: SlideSource) -> SlideSource {
+    trim_blank_lines(&mut slide.body);
+    trim_blank_lines(&mut slide.notes);
+    slide
+}
+
+fn trim_blank_lines(lines: &mut Vec<SourceLine>) {
+    while lines.last().is_some_and(|l| l.text.is_empty()) {
+        lines.pop();
+    }
+    let leading = lines.iter().take_while(|l| l.text.is_empty()).count();
+    lines.drain(..leading);
+}
+
+/// "@名前 key=value key="値 with 空白" ..." を分解する
+pub(crate) fn parse_directive(raw_line: &str, line_no: usize) -> Result<Directive, ParseError> {
+    let chars: Vec<char> = raw_line.trim_end().chars().collect();
+    let error = |column: usize, message: String| ParseError { line: line_no, column, message };
+
+    let mut i = 1; // "@" の次から
+    while i < chars.len() && !chars[i].is_whitespace() {
+        i += 1;
+    }
+    let name: String = chars[1..i].iter().collect();
+    if name.is_empty() {
+        return Err(error(2, "missing directive name after '@'".to_string()));
+    }
+
+    let mut args: Vec<DirectiveArg> = Vec::new();
+    loop {
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        if i >= chars.len() {
+            break;
+        }
+        let key_start = i;
+        while i < chars.len() && chars[i] != '=' && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        let key: String = chars[key_start..i].iter().collect();
+        if chars.get(i) != Some(&'=') {
+            return Err(error(key_start + 1, format!("expected 'key=value' but found '{}'", key)));
+        }
+        i += 1; // "="
+
+        let value: String = if chars.get(i) == Some(&'"') {
+            let close = chars[i + 1..].iter().position(|&c| c == '"').map(|p| i + 1 + p)
+                .ok_or_else(|| error(i + 1, "unterminated '\"'".to_string()))?;
+            let quoted = chars[i + 1..close].iter().collect();
+            i = close + 1;
+            quoted
+        } else {
+            let value_start = i;
+            while i < chars.len() && !chars[i].is_whitespace() {
+                i += 1;
+            }
+            chars[value_start..i].iter().collect()
+        };
+
+        if args.iter().any(|arg| arg.key == key) {
+            return Err(error(key_start + 1, format!("duplicate key '{}'", key)));
+        }
+        args.push(DirectiveArg { key, value, column: key_start + 1 });
+    }
+
+    Ok(Directive { name, line: line_no, column: 1, args })
+}
+
+/// "@layout name=title|title-body|two-column|section" を解釈する
+pub(crate) fn parse_layout(directive: &Directive) -> Result<SlideLayout, ParseError> {
+    directive.check_keys(&["name"])?;
+    let arg = directive.require("name")?;
+    SlideLayout::from_name(&arg.value).ok_or_else(|| {
+        directive.error(arg.column, format!("unknown layout '{}' (expected title, title-body, two-column or section)", arg.value))
+    })
+}
+
+/// "@background color=色 image=画像 fit=cover|contain|stretch" を解釈する。
+/// 指定しなかった項目はテーマから引き継がず、無しになる。
+pub(crate) fn parse_background(directive: &Directive, config: &DrawConfig, base_dir: &Path) -> Result<Background, ParseError> {
+    directive.check_keys(&["color", "image", "fit"])?;
+    let mut background = Background::default();
+    if let Some(arg) = directive.get("color") {
+        let color = SlideColor::parse(&arg.value, &config.palette)
+            .ok_or_else(|| directive.error(arg.column, format!("unknown color '{}'", arg.value)))?;
+        background.color = Some(color);
+    }
+    let fit = match directive.get("fit") {
+        Some(arg) => ImageFit::from_name(&arg.value)
+            .ok_or_else(|| directive.error(arg.column, format!("unknown fit '{}' (expected cover, contain or stretch)", arg.value)))?,
+        None => ImageFit::Cover,
+    };
+    match directive.get("image") {
+        Some(arg) => background.image = Some(BackgroundImage { path: base_dir.join(&arg.value), fit }),
+        None if directive.get("fit").is_some() => return Err(directive.error(directive.column, "'fit' needs an 'image'")),
+        None => {}
+    }
+    Ok(background)
+}
+
+/// "@image src=画像 col=列 row=行 width=幅 height=高さ fit=contain|cover|stretch" を解釈する
+pub(crate) fn parse_image(directive: &Directive, base_dir: &Path) -> Result<(PathBuf, ImageLayout), ParseError> {
+    directive.check_keys(&["src", "col", "row", "width", "height", "fit", "layer"])?;
+    let src = directive.require("src")?;
+    let (col, row, width, height) = parse_box(directive)?;
+    let mut layout = ImageLayout::at(col, row, width, height);
+    if let Some(arg) = directive.get("fit") {
+        layout = layout.with_fit(ImageFit::from_name(&arg.value).ok_or_else(|| {
+            directive.error(arg.column, format!("unknown fit '{}' (expected contain, cover or stretch)", arg.value))
+        })?);
+    }
+    Ok((base_dir.join(&src.value), layout))
+}
+
+/// "@rect" "@ellipse" "@line" "@arrow" を解釈する
+pub(crate) fn parse_shape(directive: &Directive, config: &DrawConfig) -> Result<Shape, ParseError> {
+    const STYLE_KEYS: [&str; 5] = ["fill", "stroke", "stroke_width", "dash", "layer"];
+    let mut shape = match directive.name.as_str() {
+        "rect" => {
+            directive.check_keys(&[&["col", "row", "width", "height", "radius"][..], &STYLE_KEYS].concat())?;
+            let (col, row, width, height) = parse_box(directive)?;
+            let radius = directive.number("radius")?.unwrap_or(0.0);
+            Shape::rounded_rect(col, row, width, height, radius)
+        }
+        "ellipse" => {
+            directive.check_keys(&[&["col", "row", "width", "height"][..], &STYLE_KEYS].concat())?;
+            let (col, row, width, height) = parse_box(directive)?;
+            Shape::ellipse(col, row, width, height)
+        }
+        _ => {
+            directive.check_keys(&[&["points", "heads"][..], &STYLE_KEYS].concat())?;
+            let points = parse_points(directive)?;
+            let mut shape = if directive.name == "arrow" { Shape::arrow(points) } else { Shape::polyline(points) };
+            if let Some(arg) = directive.get("heads") {
+                let heads = ArrowHeads::from_name(&arg.value).ok_or_else(|| {
+                    directive.error(arg.column, format!("unknown heads '{}' (expected none, start, end or both)", arg.value))
+                })?;
+                shape = shape.with_heads(heads);
+            }
+            shape
+        }
+    };
+
+    let color = |key: &str| -> Result<Option<SlideColor>, ParseError> {
+        let Some(arg) = directive.get(key) else { return Ok(None) };
+        SlideColor::parse(&arg.value, &config.palette)
+            .map(Some)
+            .ok_or_else(|| directive.error(arg.column, format!("unknown color '{}'", arg.value)))
+    };
+    if let Some(fill) = color("fill")? {
+        shape = shape.with_fill(fill);
+    }
+    match directive.get("stroke") {
+        Some(arg) if arg.value == "none" => shape = shape.without_stroke(),
+        Some(_) => shape.stroke = color("stroke")?,
+        None => {}
+    }
+    if let Some(width) = directive.number("stroke_width")? {
+        if width <= 0.0 {
+            let arg = directive.require("stroke_width")?;
+            return Err(directive.error(arg.column, format!("invalid stroke_width '{}'", arg.value)));
+        }
+        shape.stroke_width = width;
+    }
+    if let Some(arg) = directive.get("dash") {
+        let dash: Option<Vec<u32>> = arg.value.split(',').map(|v| v.trim().parse::<u32>().ok().filter(|&d| d > 0)).collect();
+        match dash {
+            Some(dash) if dash.len() <= 6 => shape = shape.with_dash(dash),
+            _ => return Err(directive.error(arg.column, format!("invalid dash '{}' (expected up to 6 whole numbers like 4,2)", arg.value))),
+        }
+    }
+    Ok(shape)
+}
+
+/// col, row, width, height を取り出す (位置は0以上、大きさは正の数)
+fn parse_box(directive: &Directive) -> Result<(f32, f32, f32, f32), ParseError> {
+    let get = |key: &str, positive: bool| -> Result<f32, ParseError> {
+        let arg = directive.require(key)?;
+        match directive.number(key)? {
+            Some(v) if v > 0.0 || (!positive && v >= 0.0) => Ok(v),
+            _ => Err(directive.error(arg.column, format!("invalid {} '{}'", key, arg.value))),
+        }
+    };
+    Ok((get("col", false)?, get("row", false)?, get("width", true)?, get("height", true)?))
+}
+
+/// points="列,行 列,行 ..." を取り出す (2点以上)
+fn parse_points(directive: &Directive) -> Result<Vec<(f32, f32)>, ParseError> {
+    let arg = directive.require("points")?;
+    let invalid = || directive.error(arg.column, format!("invalid points '{}' (expected \"col,row col,row ...\")", arg.value));
+    let points = arg
+        .value
+        .split_whitespace()
+        .map(|point| {
+            let (col, row) = point.split_once(',')?;
+            let coordinate = |v: &str| v.parse::<f32>().ok().filter(|v| v.is_finite());
+            Some((coordinate(col)?, coordinate(row)?))
+        })
+        .collect::<Option<Vec<(f32, f32)>>>()
+        .ok_or_else(invalid)?;
+    if points.len() < 2 {
+        return Err(invalid());
+    }
+    Ok(points)
+}
+
+/// "| a | b |" の行の並びを表にする。2行目が "|---|---|" なら1行目を見出しにし、":" で列の揃え方を決める
+pub(crate) fn parse_table(lines: &[SourceLine], option: Option<&Directive>, config: &DrawConfig) -> Result<Table, ParseError> {
+    let mut rows: Vec<Vec<(String, usize)>> = lines.iter().map(|line| split_table_row(&line.text).unwrap_or_default()).collect();
+    let mut source_lines: Vec<&SourceLine> = lines.iter().collect();
+    let alignments = rows.get(1).and_then(|row| parse_separator_row(row));
+    let header = alignments.is_some();
+    if header {
+        rows.remove(1);
+        source_lines.remove(1);
+    }
+
+    let mut cells_by_row: Vec<Vec<TableCell>> = Vec::with_capacity(rows.len());
+    for (row, line) in rows.iter().zip(source_lines) {
+        let mut cells = Vec::with_capacity(row.len());
+        for (col, (text, offset)) in row.iter().enumerate() {
+            let cell_line = SourceLine { text: text.clone(), column: line.column + offset, ..line.clone() };
+            let mut cell = TableCell::new(cell_line.to_contents(config, config.default_font_style, config.body_size_ratio)?);
+            cell.h_align = alignments.as_ref().and_then(|alignments| alignments.get(col).copied().flatten());
+            cells.push(cell);
+        }
+        cells_by_row.push(cells);
+    }
+
+    let mut table = Table::new(cells_by_row).with_header(header);
+    if let Some(directive) = option {
+        apply_table_options(&mut table, directive, config)?;
+    }
+    Ok(table)
+}
+
+/// "@table widths=6,auto padding=0.3 border=色|none border_width=太さ(pt) header_fill=色|none stripe=色|none" を反映する
+fn apply_table_options(table: &mut Table, directive: &Directive, config: &DrawConfig) -> Result<(), ParseError> {
+    directive.check_keys(&["widths", "padding", "border", "border_width", "header_fill", "stripe"])?;
+    if let Some(arg) = directive.get("widths") {
+        let widths: Option<Vec<ColumnWidth>> = arg
+            .value
+            .split(',')
+            .map(|width| match width.trim() {
+                "auto" => Some(ColumnWidth::Auto),
+                width => width.parse::<f32>().ok().filter(|w| *w > 0.0).map(ColumnWidth::Fixed),
+            })
+            .collect();
+        table.columns =
+            widths.ok_or_else(|| directive.error(arg.column, format!("invalid widths '{}' (expected numbers or auto, like 6,auto)", arg.value)))?;
+    }
+    if let Some(padding) = directive.number("padding")? {
+        if padding < 0.0 {
+            return Err(directive.error(directive.require("padding")?.column, format!("invalid padding '{}'", padding)));
+        }
+        table.style.padding = padding;
+    }
+    if let Some(width) = directive.number("border_width")? {
+        if width <= 0.0 {
+            return Err(directive.error(directive.require("border_width")?.column, format!("invalid border_width '{}'", width)));
+        }
+        table.style.border_width = width;
+    }
+    // 色は "none" で消せる
+    let color = |key: &str, current: Option<SlideColor>| -> Result<Option<SlideColor>, ParseError> {
+        match directive.get(key) {
+            None => Ok(current),
+            Some(arg) if arg.value == "none" => Ok(None),
+            Some(arg) => SlideColor::parse(&arg.value, &config.palette)
+                .map(Some)
+                .ok_or_else(|| directive.error(arg.column, format!("unknown color '{}'", arg.value))),
+        }
+    };
+    table.style.border = color("border", table.style.border)?;
+    table.style.header_fill = color("header_fill", table.style.header_fill)?;
+    table.style.stripe_fill = color("stripe", table.style.stripe_fill)?;
+    Ok(())
+}
+
+/// スライドファイルの "| a | b |" 形式の行をセルに分ける。
+/// 戻り値は (セルのテキスト, セルのテキストが始まる文字位置) のリスト。"\|" はセルの区切りにしない
+pub(crate) fn split_table_row(text: &str) -> Option<Vec<(String, usize)>> {
+    let chars: Vec<char> = text.trim_end().chars().collect();
+    let start = chars.iter().position(|c| !c.is_whitespace())?;
+    if chars[start] != '|' {
+        return None;
+    }
+
+    let mut cells: Vec<(String, usize)> = Vec::new();
+    let mut cell_start = start + 1;
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '|' => {
+                cells.push(trimmed_cell(&chars, cell_start, i));
+                cell_start = i + 1;
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    // 行末の "|" は省略できる
+    if cell_start < chars.len() {
+        cells.push(trimmed_cell(&chars, cell_start, chars.len()));
+    }
+    Some(cells)
+}
+
+/// chars[start..end] の前後の空白を除いたテキストと、その開始位置
+fn trimmed_cell(chars: &[char], start: usize, end: usize) -> (String, usize) {
+    let end = end.min(chars.len());
+    let first = (start..end).find(|&i| !chars[i].is_whitespace()).unwrap_or(end);
+    let last = (first..end).rev().find(|&i| !chars[i].is_whitespace()).map_or(first, |i| i + 1);
+    (chars[first..last].iter().collect(), first)
+}
+
+/// 見出し行の下の "|---|:--:|--:|" なら、列ごとの揃え方 (指定が無ければNone) を返す
+fn parse_separator_row(cells: &[(String, usize)]) -> Option<Vec<Option<HAlign>>> {
+    cells
+        .iter()
+        .map(|(text, _)| {
+            let inner = text.trim_start_matches(':').trim_end_matches(':');
+            if inner.is_empty() || !inner.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (text.starts_with(':'), text.ends_with(':')) {
+                (true, true) => Some(HAlign::Center),
+                (false, true) => Some(HAlign::Right),
+                (true, false) => Some(HAlign::Left),
+                (false, false) => None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ShapeGeometry;
+
+    fn error_at(result: Result<impl fmt::Debug, ParseError>) -> (usize, usize, String) {
+        let err = result.expect_err("expected a parse error");
+        (err.line, err.column, err.message)
+    }
+
+    fn body_texts(slide: &SlideSource) -> Vec<&str> {
+        slide.body.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn directive_args_keep_their_columns() {
+        let directive = parse_directive(r#"@image src="my pic.png" col=2  width=日本"#, 7).unwrap();
+        assert_eq!(directive.name, "image");
+        assert_eq!(directive.line, 7);
+        let args: Vec<(&str, &str, usize)> = directive.args.iter().map(|arg| (arg.key.as_str(), arg.value.as_str(), arg.column)).collect();
+        assert_eq!(args, [("src", "my pic.png", 8), ("col", "2", 25), ("width", "日本", 32)]);
+        assert!(parse_directive("@column   ", 1).unwrap().args.is_empty());
+    }
+
+    #[test]
+    fn directive_syntax_errors() {
+        assert_eq!(error_at(parse_directive("@", 3)), (3, 2, "missing directive name after '@'".to_string()));
+        assert_eq!(error_at(parse_directive("@rect col", 3)), (3, 7, "expected 'key=value' but found 'col'".to_string()));
+        assert_eq!(error_at(parse_directive("@rect col=1 col=2", 3)), (3, 13, "duplicate key 'col'".to_string()));
+        assert_eq!(error_at(parse_directive(r#"@image src="abc"#, 3)), (3, 12, "unterminated '\"'".to_string()));
+        // 桁位置はバイトではなく文字で数える
+        assert_eq!(error_at(parse_directive("@rect 色 col=1", 3)), (3, 7, "expected 'key=value' but found '色'".to_string()));
+    }
+
+    #[test]
+    fn directive_values() {
+        let directive = parse_directive("@rect col=1.5 row=x fill=red", 4).unwrap();
+        assert_eq!(directive.number("col").unwrap(), Some(1.5));
+        assert_eq!(directive.number("width").unwrap(), None);
+        assert_eq!(error_at(directive.number("row")), (4, 15, "invalid row 'x'".to_string()));
+        assert_eq!(error_at(directive.require("width")), (4, 1, "@rect needs 'width'".to_string()));
+        assert_eq!(
+            error_at(directive.check_keys(&["col", "row"])),
+            (4, 21, "unknown key 'fill' for @rect (expected one of: col, row)".to_string())
+        );
+        assert!(directive.check_keys(&["col", "row", "fill"]).is_ok());
+        assert_eq!(error_at(parse_directive("@rect col=inf", 1).unwrap().number("col")), (1, 7, "invalid col 'inf'".to_string()));
+    }
+
+    #[test]
+    fn markup_errors_map_to_file_columns() {
+        let config = DrawConfig::from_grid(24.0, 32.0, 18.0);
+        let line = SourceLine { text: "ab{/size}".to_string(), line: 9, column: 5 };
+        let err = line.to_contents(&config, FontStyle::Regular, 1.0).unwrap_err();
+        assert_eq!((err.line, err.column), (9, 7));
+    }
+
+    #[test]
+    fn slides_are_split_on_separator_lines() {
+        let slides = parse_deck("# A\n## a\nbody\n---\n# B\n\n  \none\n\n---  \n# C\n---\n").unwrap();
+        assert_eq!(slides.len(), 3);
+        assert_eq!(slides[0].title.as_ref().map(|line| line.text.as_str()), Some("A"));
+        assert_eq!(slides[0].subtitle.as_ref().map(|line| line.text.as_str()), Some("a"));
+        assert_eq!(body_texts(&slides[0]), ["body"]);
+        // 本文の先頭と末尾の空行は捨て、行番号はファイル上のまま
+        assert_eq!(body_texts(&slides[1]), ["one"]);
+        assert_eq!(slides[1].body[0].line, 8);
+        assert_eq!(slides[2].title.as_ref().map(|line| line.line), Some(11));
+    }
+
+    #[test]
+    fn empty_input_is_one_empty_slide() {
+        let slides = parse_deck("").unwrap();
+        assert_eq!(slides.len(), 1);
+        assert!(slides[0].title.is_none() && slides[0].body.is_empty());
+        assert_eq!(parse_deck("// only a comment\n").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn code_blocks_keep_separators_and_comments() {
+        let slides = parse_deck("```sh\n---\n// not a comment\n# not a title\n```\n---\nnext").unwrap();
+        assert_eq!(slides.len(), 2);
+        assert_eq!(body_texts(&slides[0]), ["```sh", "---", "// not a comment", "# not a title", "```"]);
+        assert_eq!(error_at(parse_deck("text\n```rust\nfn main() {}\n")), (2, 1, "unterminated code block (missing closing ```)".to_string()));
+    }
+
+    #[test]
+    fn headings() {
+        let slides = parse_deck("#   Title  \n##Sub").unwrap();
+        let title = slides[0].title.as_ref().unwrap();
+        assert_eq!((title.text.as_str(), title.column), ("Title", 5));
+        assert_eq!(slides[0].subtitle.as_ref().map(|line| line.column), Some(3));
+
+        assert_eq!(error_at(parse_deck("# A\n# B")), (2, 1, "slide already has a title".to_string()));
+        assert_eq!(error_at(parse_deck("body\n# A")), (2, 1, "title must come before body text".to_string()));
+        assert_eq!(error_at(parse_deck("text\n---\n#   ")), (3, 5, "empty title".to_string()));
+        // タイトルより前の空行は本文に残らない
+        assert!(parse_deck("\n\n# A").unwrap()[0].body.is_empty());
+    }
+
+    #[test]
+    fn directives_are_sorted_into_the_slide() {
+        let input = "@background color=red\n@rect col=1 row=1 width=2 height=2\n@table border=none\n| a |\n@column\n@image src=a.png\n\\@ not a directive";
+        let slides = parse_deck(input).unwrap();
+        let slide = &slides[0];
+        assert!(slide.background.is_some());
+        let elements: Vec<&str> = slide.elements.iter().map(|directive| directive.name.as_str()).collect();
+        assert_eq!(elements, ["rect", "image"]);
+        assert_eq!(slide.tables.len(), 1);
+        assert_eq!(slide.column_breaks.len(), 1);
+        assert_eq!(body_texts(slide), ["| a |", "\\@ not a directive"]);
+
+        assert_eq!(error_at(parse_deck("@background color=red\n@background color=blue")), (2, 1, "slide already has a @background".to_string()));
+        assert_eq!(error_at(parse_deck("text\n@frame")), (2, 2, "unknown directive '@frame'".to_string()));
+    }
+
+    #[test]
+    fn notes_run_until_the_next_separator() {
+        let slides = parse_deck("# A\n@notes\n\n# not a title\n@not a directive\n// comment\nlast\n\n---\n# B").unwrap();
+        assert_eq!(slides.len(), 2);
+        let notes: Vec<(&str, usize)> = slides[0].notes.iter().map(|line| (line.text.as_str(), line.line)).collect();
+        assert_eq!(notes, [("# not a title", 4), ("@not a directive", 5), ("last", 7)]);
+        assert!(slides[1].notes.is_empty());
+        // ノートだけのスライドも作る
+        assert_eq!(parse_deck("a\n---\n@notes\nonly notes").unwrap().len(), 2);
+        assert_eq!(error_at(parse_deck("@notes all=yes")), (1, 8, "@notes takes no keys".to_string()));
+    }
+
+    fn table_lines(rows: &[&str]) -> Vec<SourceLine> {
+        rows.iter().enumerate().map(|(i, text)| SourceLine { text: text.to_string(), line: i + 10, column: 1 }).collect()
+    }
+
+    fn parse_rows(rows: &[&str], option: Option<&str>) -> Result<Table, ParseError> {
+        let config = DrawConfig::from_grid(24.0, 32.0, 18.0);
+        let directive = option.map(|option| parse_directive(option, 9).unwrap());
+        parse_table(&table_lines(rows), directive.as_ref(), &config)
+    }
+
+    #[test]
+    fn table_rows_split_on_unescaped_bars() {
+        assert_eq!(split_table_row("plain"), None);
+        assert_eq!(split_table_row("  | a |b|"), Some(vec![("a".to_string(), 4), ("b".to_string(), 7)]));
+        // 行末の "|" は省略でき、"\|" はセルの中の文字になる
+        assert_eq!(split_table_row(r"| a \| b | c"), Some(vec![(r"a \| b".to_string(), 2), ("c".to_string(), 11)]));
+        assert_eq!(split_table_row("| |"), Some(vec![(String::new(), 2)]));
+    }
+
+    #[test]
+    fn separator_row_makes_a_header_and_sets_alignment() {
+        let table = parse_rows(&["| a | b | c | d |", "|:--|:-:|--:|---|", "| 1 | 2 |"], None).unwrap();
+        assert!(table.header);
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.rows[1].len(), 2);
+        let aligns: Vec<Option<HAlign>> = table.rows[0].iter().map(|cell| cell.h_align).collect();
+        assert!(matches!(aligns[..], [Some(HAlign::Left), Some(HAlign::Center), Some(HAlign::Right), None]));
+        assert!(matches!(table.rows[1][1].h_align, Some(HAlign::Center)));
+    }
+
+    #[test]
+    fn tables_without_a_separator_have_no_header() {
+        let table = parse_rows(&["| a | b |", "| -- | x |"], None).unwrap();
+        assert!(!table.header);
+        assert_eq!(table.rows.len(), 2);
+        assert!(table.rows[0].iter().all(|cell| cell.h_align.is_none()));
+    }
+
+    #[test]
+    fn cell_markup_errors_point_into_the_cell() {
+        assert_eq!(error_at(parse_rows(&["| a | b |", "| ok | {/size} |"], None)), (11, 8, "'{/size}' has no opening tag".to_string()));
+    }
+
+    #[test]
+    fn table_options() {
+        let option = "@table widths=6,auto padding=0 border=none border_width=2 header_fill=#ff0000 stripe=blue";
+        let table = parse_rows(&["| a | b |"], Some(option)).unwrap();
+        assert_eq!(table.columns, [ColumnWidth::Fixed(6.0), ColumnWidth::Auto]);
+        assert_eq!(table.style.padding, 0.0);
+        assert_eq!(table.style.border_width, 2.0);
+        assert!(table.style.border.is_none());
+        assert!(matches!(table.style.header_fill, Some(SlideColor::Custom(r, g, _)) if r == 1.0 && g == 0.0));
+        assert!(table.style.stripe_fill.is_some());
+
+        let table = parse_rows(&["| a |"], Some("@table header_fill=none")).unwrap();
+        assert!(table.style.header_fill.is_none());
+        assert!(table.style.border.is_some());
+    }
+
+    #[test]
+    fn table_option_errors() {
+        let rows = ["| a |"];
+        for (option, column, message) in [
+            ("@table widths=6,0", 8, "invalid widths '6,0' (expected numbers or auto, like 6,auto)"),
+            ("@table padding=-1", 8, "invalid padding '-1'"),
+            ("@table border_width=0", 8, "invalid border_width '0'"),
+            ("@table stripe=mauve", 8, "unknown color 'mauve'"),
+            ("@table align=left", 8, "unknown key 'align' for @table (expected one of: widths, padding, border, border_width, header_fill, stripe)"),
+        ] {
+            assert_eq!(error_at(parse_rows(&rows, Some(option))), (9, column, message.to_string()), "{}", option);
+        }
+    }
+
+    fn shape(line: &str) -> Result<Shape, ParseError> {
+        parse_shape(&parse_directive(line, 4)?, &DrawConfig::from_grid(24.0, 32.0, 18.0))
+    }
+
+    #[test]
+    fn shapes() {
+        let rect = shape("@rect col=1 row=2 width=3 height=4 radius=0.5 fill=red stroke=none dash=4,2").unwrap();
+        assert!(matches!(rect.geometry, ShapeGeometry::Rect { col: 1.0, row: 2.0, width: 3.0, height: 4.0, radius: 0.5 }));
+        assert!(rect.fill.is_some() && rect.stroke.is_none());
+        assert_eq!(rect.dash, [4, 2]);
+
+        let ellipse = shape("@ellipse col=0 row=0 width=2 height=1 stroke=blue stroke_width=3").unwrap();
+        assert!(matches!(ellipse.geometry, ShapeGeometry::Ellipse { .. }));
+        assert_eq!(ellipse.stroke_width, 3.0);
+
+        let arrow = shape(r#"@arrow points="0,0 2.5,3 4,0""#).unwrap();
+        assert!(matches!(&arrow.geometry, ShapeGeometry::Path { points, heads: ArrowHeads::End } if points == &[(0.0, 0.0), (2.5, 3.0), (4.0, 0.0)]));
+        let line = shape(r#"@line points="0,0 1,1" heads=both"#).unwrap();
+        assert!(matches!(line.geometry, ShapeGeometry::Path { heads: ArrowHeads::Both, .. }));
+    }
+
+    #[test]
+    fn bad_coordinates_point_at_their_key() {
+        for (line, column, message) in [
+            ("@rect col=-1 row=0 width=1 height=1", 7, "invalid col '-1'"),
+            ("@rect col=1 row=x width=1 height=1", 13, "invalid row 'x'"),
+            ("@rect col=1 row=0 width=0 height=1", 19, "invalid width '0'"),
+            ("@ellipse col=1 row=0 width=1 height=-2", 30, "invalid height '-2'"),
+            ("@rect col=1 row=0 width=1", 1, "@rect needs 'height'"),
+            (r#"@line points="0,0 3""#, 7, r#"invalid points '0,0 3' (expected "col,row col,row ...")"#),
+            (r#"@arrow points="1,1""#, 8, r#"invalid points '1,1' (expected "col,row col,row ...")"#),
+            (r#"@line heads=end points="0,0 1,y""#, 17, r#"invalid points '0,0 1,y' (expected "col,row col,row ...")"#),
+            (r#"@line points="nan,1 inf,2""#, 7, r#"invalid points 'nan,1 inf,2' (expected "col,row col,row ...")"#),
+            (r#"@arrow points="0,0 1,-inf""#, 8, r#"invalid points '0,0 1,-inf' (expected "col,row col,row ...")"#),
+            ("@line heads=end", 1, "@line needs 'points'"),
+        ] {
+            assert_eq!(error_at(shape(line)), (4, column, message.to_string()), "{}", line);
+        }
+    }
+
+    #[test]
+    fn bad_shape_styles() {
+        for (line, column, message) in [
+            ("@rect col=0 row=0 width=1 height=1 fill=mauve", 36, "unknown color 'mauve'"),
+            ("@rect col=0 row=0 width=1 height=1 stroke_width=0", 36, "invalid stroke_width '0'"),
+            ("@rect col=0 row=0 width=1 height=1 dash=4,0", 36, "invalid dash '4,0' (expected up to 6 whole numbers like 4,2)"),
+            ("@rect col=0 row=0 width=1 height=1 dash=1,2,3,4,5,6,7", 36, "invalid dash '1,2,3,4,5,6,7' (expected up to 6 whole numbers like 4,2)"),
+            (r#"@line points="0,0 1,1" heads=up"#, 24, "unknown heads 'up' (expected none, start, end or both)"),
+            (
+                "@ellipse col=0 row=0 width=1 height=1 radius=1",
+                39,
+                "unknown key 'radius' for @ellipse (expected one of: col, row, width, height, fill, stroke, stroke_width, dash, layer)",
+            ),
+        ] {
+            assert_eq!(error_at(shape(line)), (4, column, message.to_string()), "{}", line);
+        }
+    }
+
+    #[test]
+    fn backgrounds() {
+        let config = DrawConfig::from_grid(24.0, 32.0, 18.0);
+        let parse = |line: &str| parse_background(&parse_directive(line, 2).unwrap(), &config, Path::new("decks"));
+
+        let background = parse("@background color=blue image=bg.png fit=contain").unwrap();
+        assert!(background.color.is_some());
+        let image = background.image.unwrap();
+        assert_eq!((image.path.as_path(), image.fit), (Path::new("decks/bg.png"), ImageFit::Contain));
+        // 指定しなかった項目は無しになり、画像は既定で cover
+        let background = parse("@background image=bg.png").unwrap();
+        assert!(background.color.is_none());
+        assert_eq!(background.image.map(|image| image.fit), Some(ImageFit::Cover));
+
+        assert_eq!(error_at(parse("@background fit=cover")), (2, 1, "'fit' needs an 'image'".to_string()));
+        assert_eq!(error_at(parse("@background color=mauve")), (2, 13, "unknown color 'mauve'".to_string()));
+        assert_eq!(
+            error_at(parse("@background image=a.png fit=tile")),
+            (2, 25, "unknown fit 'tile' (expected cover, contain or stretch)".to_string())
+        );
+    }
+
+    #[test]
+    fn images() {
+        let parse = |line: &str| parse_image(&parse_directive(line, 6).unwrap(), Path::new("decks"));
+        let (path, layout) = parse("@image src=a.png col=1 row=2 width=3 height=4").unwrap();
+        assert_eq!(path, Path::new("decks/a.png"));
+        assert_eq!((layout.start_col, layout.start_row, layout.width, layout.height), (1.0, 2.0, 3.0, 4.0));
+        assert_eq!(layout.fit, ImageFit::Contain);
+        assert_eq!(parse("@image src=a.png col=0 row=0 width=1 height=1 fit=stretch").unwrap().1.fit, ImageFit::Stretch);
+
+        assert_eq!(error_at(parse("@image col=0 row=0 width=1 height=1")), (6, 1, "@image needs 'src'".to_string()));
+        assert_eq!(error_at(parse("@image src=a.png col=0 row=0 width=1 height=0")), (6, 38, "invalid height '0'".to_string()));
+        assert_eq!(
+            error_at(parse("@image src=a.png col=0 row=0 width=1 height=1 fit=fill")),
+            (6, 47, "unknown fit 'fill' (expected contain, cover or stretch)".to_string())
+        );
+    }
+
+    #[test]
+    fn layouts() {
+        let parse = |line: &str| parse_layout(&parse_directive(line, 1).unwrap());
+        assert_eq!(parse("@layout name=two-column").unwrap(), SlideLayout::TwoColumn);
+        assert_eq!(
+            error_at(parse("@layout name=grid")),
+            (1, 9, "unknown layout 'grid' (expected title, title-body, two-column or section)".to_string())
+        );
+        assert_eq!(error_at(parse("@layout")), (1, 1, "@layout needs 'name'".to_string()));
+    }
+}
@@ -0,0 +1,126 @@
+// --- PDFへの出力 ---
+//
+// レイアウト済みのページ (page.rs) を printpdf の描画命令に変換し、1ページずつPDFに加える。
+
+use crate::error::SlideError;
+use crate::font::{font_for, LoadedFont};
+use crate::image::{image_ops, ImageCache};
+use crate::page::{DrawOp, Frame, Page, PathPoint, Renderer};
+use crate::{FontStyle, LinkTarget};
+use printpdf::{
+    Actions, BorderArray, ColorArray, Destination, Line, LineDashPattern, LinePoint, LinkAnnotation, Mm, Op, PaintMode,
+    PdfDocument, PdfPage, PdfSaveOptions, PdfWarnMsg, Point, Polygon, PolygonRing, Pt, Rect, TextItem, WindingOrder,
+};
+use std::collections::HashMap;
+
+/// ページをPDFのページにしていく。画像は最初に渡したドキュメントに登録する
+pub(crate) struct PdfRenderer<'a> {
+    doc: PdfDocument,
+    fonts: &'a HashMap<FontStyle, LoadedFont>,
+    images: ImageCache,
+    pages: Vec<PdfPage>,
+    warnings: &'a mut Vec<PdfWarnMsg>,
+}
+
+impl<'a> PdfRenderer<'a> {
+    pub(crate) fn new(doc: PdfDocument, fonts: &'a HashMap<FontStyle, LoadedFont>, warnings: &'a mut Vec<PdfWarnMsg>) -> Self {
+        PdfRenderer { doc, fonts, images: ImageCache::default(), pages: Vec::new(), warnings }
+    }
+
+    /// 描いたページをドキュメントに追加して保存する
+    pub(crate) fn save(mut self) -> Vec<u8> {
+        let save_opts: PdfSaveOptions = PdfSaveOptions { subset_fonts: true, ..Default::default() };
+        self.doc.with_pages(self.pages).save(&save_opts, self.warnings)
+    }
+}
+
+impl Renderer for PdfRenderer<'_> {
+    fn render_page(&mut self, page: &Page) -> Result<(), SlideError> {
+        let mut ops: Vec<Op> = Vec::new();
+        for op in &page.ops {
+            match op {
+                DrawOp::Text { x, baseline, style, size, color, letter_spacing, text } => {
+                    let font = &font_for(self.fonts, *style)?.id;
+                    ops.extend([
+                        Op::StartTextSection,
+                        Op::SetFillColor { col: color.into_pdf_color() },
+                        Op::SetTextCursor { pos: Point { x: Pt(*x), y: Pt(*baseline) } },
+                        Op::SetFontSize { size: Pt(*size), font: font.clone() },
+                        // Tcはテキストセクションをまたいで残るため、0のときも毎回設定する
+                        Op::SetCharacterSpacing { multiplier: *letter_spacing },
+                        Op::WriteText { items: vec![TextItem::Text(text.clone())], font: font.clone() },
+                        Op::EndTextSection,
+                    ]);
+                }
+                DrawOp::Path { points, closed, fill, stroke } => {
+                    ops.push(Op::SaveGraphicsState);
+                    if let Some(fill) = fill {
+                        ops.push(Op::SetFillColor { col: fill.into_pdf_color() });
+                    }
+                    if let Some(stroke) = stroke {
+                        ops.push(Op::SetOutlineColor { col: stroke.color.into_pdf_color() });
+                        ops.push(Op::SetOutlineThickness { pt: Pt(stroke.width) });
+                        if !stroke.dash.is_empty() {
+                            let dash: Vec<i64> = stroke.dash.iter().map(|&d| i64::from(d)).collect();
+                            ops.push(Op::SetLineDashPattern { dash: LineDashPattern::from_array(&dash, 0) });
+                        }
+                    }
+                    let points: Vec<LinePoint> = points.iter().map(line_point).collect();
+                    if *closed {
+                        let mode = if stroke.is_none() {
+                            PaintMode::Fill
+                        } else if fill.is_none() {
+                            PaintMode::Stroke
+                        } else {
+                            PaintMode::FillStroke
+                        };
+                        ops.push(Op::DrawPolygon { polygon: polygon(points, mode) });
+                    } else {
+                        ops.push(Op::DrawLine { line: Line { points, is_closed: false } });
+                    }
+                    ops.push(Op::RestoreGraphicsState);
+                }
+                DrawOp::Image { path, frame, fit } => {
+                    let placed = self.images.load(&mut self.doc, path, self.warnings)?;
+                    ops.extend(image_ops(&placed, *frame, *fit));
+                }
+                DrawOp::PushClip(frame) => {
+                    ops.push(Op::SaveGraphicsState);
+                    ops.push(Op::DrawPolygon { polygon: rect_polygon(*frame, PaintMode::Clip) });
+                }
+                DrawOp::PopClip => ops.push(Op::RestoreGraphicsState),
+                DrawOp::Link { frame, target } => {
+                    let actions = match target {
+                        LinkTarget::Uri(uri) => Actions::uri(uri.clone()),
+                        LinkTarget::Slide(page) => Actions::go_to(Destination::Xyz { page: *page, left: None, top: None, zoom: None }),
+                    };
+                    let rect = Rect { x: Pt(frame.x), y: Pt(frame.y_top - frame.height), width: Pt(frame.width), height: Pt(frame.height) };
+                    // 枠線は描かない
+                    let link = LinkAnnotation::new(rect, actions, Some(BorderArray::Solid([0.0, 0.0, 0.0])), Some(ColorArray::Transparent), None);
+                    ops.push(Op::LinkAnnotation { link });
+                }
+            }
+        }
+        let (width, height): (Mm, Mm) = (Pt(page.width).into(), Pt(page.height).into());
+        self.pages.push(PdfPage::new(width, height, ops));
+        Ok(())
+    }
+}
+
+/// `frame` の長方形
+pub(crate) fn rect_polygon(frame: Frame, mode: PaintMode) -> Polygon {
+    let (left, right, top, bottom) = (frame.x, frame.x + frame.width, frame.y_top, frame.y_top - frame.height);
+    let points = [(left, top), (right, top), (right, bottom), (left, bottom)]
+        .iter()
+        .map(|&(x, y)| line_point(&PathPoint { x, y, bezier: false }))
+        .collect();
+    polygon(points, mode)
+}
+
+fn polygon(points: Vec<LinePoint>, mode: PaintMode) -> Polygon {
+    Polygon { rings: vec![PolygonRing { points }], mode, winding_order: WindingOrder::NonZero }
+}
+
+fn line_point(point: &PathPoint) -> LinePoint {
+    LinePoint { p: Point { x: Pt(point.x), y: Pt(point.y) }, bezier: point.bezier }
+}
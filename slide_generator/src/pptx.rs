@@ -0,0 +1,658 @@
+// --- PowerPointファイル (PPTX) への出力 ---
+//
+// Office Open XML のパッケージ (ZIP) を書く。テキストブロックは1つのテキストボックスにし、
+// 行を段落、Spanをランにする (フォント・大きさ・色・リンクをランごとに保つ)。
+// リストや表、図形、画像はPDFと同じ描画命令 (page.rs) にしてから、
+// 文字は1行ずつのテキストボックス、パスは自由図形、画像は図として置く。
+// 位置はグリッドから求めたpt単位の座標を EMU (1pt = 12700) に直して使う。
+
+use crate::error::SlideError;
+use crate::font::{font_for, LoadedFont};
+use crate::image::fit_frame;
+use crate::page::{DrawOp, Frame, PathPoint, Stroke};
+use crate::svg::escape;
+use crate::{BlockLayout, Content, DrawConfig, FontStyle, HAlign, ImageFit, LinkTarget, SlideColor};
+use printpdf::RawImage;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{Cursor, Write as _};
+use std::path::{Path, PathBuf};
+use ttf_parser::name_id;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// 1pt あたりの EMU
+const EMU_PER_PT: f32 = 12700.0;
+
+const NS_A: &str = "http://schemas.openxmlformats.org/drawingml/2006/main";
+const NS_R: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const NS_P: &str = "http://schemas.openxmlformats.org/presentationml/2006/main";
+const NS_PACKAGE_RELS: &str = "http://schemas.openxmlformats.org/package/2006/relationships";
+const XML_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n";
+
+/// ラン1つ分の文字。size と letter_spacing はpt
+#[derive(Debug, Clone)]
+pub(crate) struct Run {
+    pub text: String,
+    pub style: FontStyle,
+    pub size: f32,
+    pub color: SlideColor,
+    pub letter_spacing: f32,
+    pub link: Option<LinkTarget>,
+    pub underline: bool,
+}
+
+/// 段落1つ分。line_pitch は行送り (pt)
+#[derive(Debug, Clone)]
+pub(crate) struct Paragraph {
+    pub runs: Vec<Run>,
+    pub line_pitch: f32,
+}
+
+/// frame の中に段落を上から並べるテキストボックス。wrap なら枠の幅で折り返す
+#[derive(Debug, Clone)]
+pub(crate) struct TextBox {
+    pub frame: Frame,
+    pub paragraphs: Vec<Paragraph>,
+    pub align: HAlign,
+    pub wrap: bool,
+}
+
+/// スライドに置くもの。後のものほど手前に置く
+#[derive(Debug, Clone)]
+pub(crate) enum PptxItem {
+    TextBox(TextBox),
+    /// 描画命令 (クリップとリンクの範囲は使わない)
+    Draw(DrawOp),
+}
+
+/// テキストブロックを、描いたときと同じ位置・行送りのテキストボックスにする。`end_row` は draw_text_block の戻り値
+pub(crate) fn text_box(config: &DrawConfig, contents: &[Content], layout: &BlockLayout, end_row: f32) -> PptxItem {
+    let grid = config.base_font_size.0;
+    let mut paragraphs = vec![Paragraph { runs: Vec::new(), line_pitch: 0.0 }];
+    for content in contents {
+        match content {
+            Content::Span(span) => paragraphs.last_mut().expect("at least one paragraph").runs.push(Run {
+                text: span.text.clone(),
+                style: span.style,
+                size: span.size_ratio * grid,
+                color: span.color,
+                letter_spacing: 0.0,
+                link: span.link.as_ref().map(|link| link.target.clone()),
+                underline: span.link.as_ref().is_some_and(|link| link.underline),
+            }),
+            Content::Newline => paragraphs.push(Paragraph { runs: Vec::new(), line_pitch: 0.0 }),
+        }
+    }
+    // 行送りは draw_text_block と同じく、行で一番大きい文字 (最低でも基準サイズ) に行間の比率を掛ける
+    for paragraph in &mut paragraphs {
+        let max_size = paragraph.runs.iter().map(|run| run.size).fold(grid, f32::max);
+        paragraph.line_pitch = max_size * layout.line_spacing_ratio;
+    }
+    let x = layout.start_col * grid;
+    let frame = Frame {
+        x,
+        y_top: config.page_height_pt.0 - layout.start_row * grid,
+        // 幅が無いブロックは折り返さないので、ページの右端までを枠にする
+        width: layout.width.map_or(config.page_width_pt.0 - x, |width| width * grid),
+        height: (end_row - layout.start_row) * grid,
+    };
+    // 幅が無いときは水平揃えもしない (draw_text_block と同じ)
+    let align = if layout.width.is_some() { layout.h_align } else { HAlign::Left };
+    PptxItem::TextBox(TextBox { frame, paragraphs, align, wrap: layout.width.is_some() })
+}
+
+/// スライドのXMLと、そこから参照するファイル
+struct SlidePart {
+    number: usize, // 1始まりのスライド番号
+    xml: String,
+    rels: Vec<(String, String, bool)>, // (関係の種類, 参照先, 外部のURIか)
+}
+
+/// スライドを1枚ずつPPTXのスライドにしていく
+pub(crate) struct PptxWriter<'a> {
+    fonts: &'a HashMap<FontStyle, LoadedFont>,
+    families: HashMap<FontStyle, String>, // フォントファイルのファミリー名 (PowerPointでの書体名)
+    width: f32,
+    height: f32,
+    media: Vec<(PathBuf, String, f32, f32)>, // (画像ファイル, パッケージ内の名前, 幅px, 高さpx)
+    slides: Vec<SlidePart>,
+    slide_links: Vec<(usize, usize)>, // (リンク元のスライド番号, 飛び先のスライド番号)。最後に飛び先があるか確かめる
+}
+
+impl<'a> PptxWriter<'a> {
+    pub(crate) fn new(fonts: &'a HashMap<FontStyle, LoadedFont>, width: f32, height: f32) -> Result<Self, SlideError> {
+        let mut families = HashMap::new();
+        for (style, font) in fonts {
+            families.insert(*style, family_name(font)?);
+        }
+        Ok(PptxWriter { fonts, families, width, height, media: Vec::new(), slides: Vec::new(), slide_links: Vec::new() })
+    }
+
+    /// 1枚分のスライドを追加する。`background` はスライド全体の塗りの色
+    pub(crate) fn add_slide(&mut self, background: Option<SlideColor>, items: &[PptxItem]) -> Result<(), SlideError> {
+        let mut part = SlidePart { number: self.slides.len() + 1, xml: String::new(), rels: Vec::new() };
+        rel(&mut part, "slideLayout", "../slideLayouts/slideLayout1.xml", false);
+
+        // リストや表のリンクは、リンクの範囲の直前に描いた文字のランに付ける (link_ops はSpanのすぐ後に置かれる)
+        let mut links: HashMap<usize, LinkTarget> = HashMap::new();
+        let mut last_text: Option<(usize, f32)> = None;
+        for (index, item) in items.iter().enumerate() {
+            match item {
+                PptxItem::Draw(DrawOp::Text { x, .. }) => last_text = Some((index, *x)),
+                PptxItem::Draw(DrawOp::Link { frame, target }) => {
+                    if let Some((text, x)) = last_text
+                        && (x - frame.x).abs() < 0.01
+                    {
+                        links.insert(text, target.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut shapes = String::new();
+        let mut id = 1;
+        for (index, item) in items.iter().enumerate() {
+            match item {
+                PptxItem::TextBox(text) => {
+                    id += 1;
+                    self.write_text_box(&mut shapes, &mut part, id, text)?;
+                }
+                PptxItem::Draw(DrawOp::Text { x, baseline, style, size, color, letter_spacing, text }) => {
+                    // 1行だけのテキストボックス。枠の上端は行の上端 (ベースラインからフォントサイズ分上)
+                    let width = font_for(self.fonts, *style)?.text_width_em(text) * size + letter_spacing * text.chars().count() as f32;
+                    let frame = Frame { x: *x, y_top: baseline + size, width, height: size * 1.2 };
+                    let run = Run {
+                        text: text.clone(),
+                        style: *style,
+                        size: *size,
+                        color: *color,
+                        letter_spacing: *letter_spacing,
+                        link: links.remove(&index),
+                        underline: false, // 下線は別の図形として描かれている
+                    };
+                    let text = TextBox { frame, paragraphs: vec![Paragraph { runs: vec![run], line_pitch: size * 1.2 }], align: HAlign::Left, wrap: false };
+                    id += 1;
+                    self.write_text_box(&mut shapes, &mut part, id, &text)?;
+                }
+                PptxItem::Draw(DrawOp::Path { points, closed, fill, stroke }) => {
+                    id += 1;
+                    self.write_path(&mut shapes, id, points, *closed, *fill, stroke.as_ref());
+                }
+                PptxItem::Draw(DrawOp::Image { path, frame, fit }) => {
+                    id += 1;
+                    self.write_picture(&mut shapes, &mut part, id, path, *frame, *fit)?;
+                }
+                PptxItem::Draw(DrawOp::PushClip(_) | DrawOp::PopClip | DrawOp::Link { .. }) => {}
+            }
+        }
+
+        let xml = &mut part.xml;
+        xml.push_str(XML_HEADER);
+        let _ = write!(xml, "<p:sld xmlns:a=\"{}\" xmlns:r=\"{}\" xmlns:p=\"{}\"><p:cSld>", NS_A, NS_R, NS_P);
+        if let Some(color) = background {
+            let _ = write!(xml, "<p:bg><p:bgPr>{}<a:effectLst/></p:bgPr></p:bg>", solid_fill(color));
+        }
+        xml.push_str("<p:spTree>");
+        xml.push_str(GROUP_PROPERTIES);
+        xml.push_str(&shapes);
+        xml.push_str("</p:spTree></p:cSld><p:clrMapOvr><a:masterClrMapping/></p:clrMapOvr></p:sld>");
+        self.slides.push(part);
+        Ok(())
+    }
+
+    /// `title` を文書のタイトルにして、PPTXファイルのバイト列にする
+    pub(crate) fn finish(self, title: &str) -> Result<Vec<u8>, SlideError> {
+        let slides = self.slides.len();
+        if let Some(&(slide, target)) = self.slide_links.iter().find(|(_, target)| *target > slides) {
+            return Err(SlideError::BrokenLink { slide, target, slides });
+        }
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut add = |name: &str, bytes: &[u8]| -> Result<(), SlideError> {
+            zip.start_file(name, options).map_err(|e| SlideError::Pptx(e.to_string()))?;
+            zip.write_all(bytes).map_err(|e| SlideError::Pptx(e.to_string()))
+        };
+
+        // --- パッケージの目次 ---
+        let mut types = String::from(XML_HEADER);
+        types.push_str("<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">");
+        types.push_str("<Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>");
+        types.push_str("<Default Extension=\"xml\" ContentType=\"application/xml\"/>");
+        types.push_str("<Default Extension=\"png\" ContentType=\"image/png\"/>");
+        types.push_str("<Default Extension=\"jpeg\" ContentType=\"image/jpeg\"/>");
+        let overrides = [
+            ("/ppt/presentation.xml", "presentationml.presentation.main+xml"),
+            ("/ppt/slideMasters/slideMaster1.xml", "presentationml.slideMaster+xml"),
+            ("/ppt/slideLayouts/slideLayout1.xml", "presentationml.slideLayout+xml"),
+            ("/ppt/theme/theme1.xml", "theme+xml"),
+        ];
+        for (part, kind) in overrides {
+            let _ = write!(types, "<Override PartName=\"{}\" ContentType=\"application/vnd.openxmlformats-officedocument.{}\"/>", part, kind);
+        }
+        for number in 1..=slides {
+            let _ = write!(
+                types,
+                "<Override PartName=\"/ppt/slides/slide{}.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.presentationml.slide+xml\"/>",
+                number
+            );
+        }
+        types.push_str("<Override PartName=\"/docProps/core.xml\" ContentType=\"application/vnd.openxmlformats-package.core-properties+xml\"/>");
+        types.push_str("</Types>");
+        add("[Content_Types].xml", types.as_bytes())?;
+
+        add(
+            "_rels/.rels",
+            relationships(&[
+                (format!("{}/officeDocument", NS_R), "ppt/presentation.xml".to_string(), false),
+                (format!("{}/metadata/core-properties", NS_PACKAGE_RELS), "docProps/core.xml".to_string(), false),
+            ])
+            .as_bytes(),
+        )?;
+        let core = format!(
+            "{}<cp:coreProperties xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\"><dc:title>{}</dc:title></cp:coreProperties>",
+            XML_HEADER,
+            escape(title)
+        );
+        add("docProps/core.xml", core.as_bytes())?;
+
+        // --- プレゼンテーション本体 (スライドの並びと大きさ) ---
+        let mut presentation = String::from(XML_HEADER);
+        let _ = write!(presentation, "<p:presentation xmlns:a=\"{}\" xmlns:r=\"{}\" xmlns:p=\"{}\">", NS_A, NS_R, NS_P);
+        presentation.push_str("<p:sldMasterIdLst><p:sldMasterId id=\"2147483648\" r:id=\"rId1\"/></p:sldMasterIdLst><p:sldIdLst>");
+        for number in 1..=slides {
+            let _ = write!(presentation, "<p:sldId id=\"{}\" r:id=\"rId{}\"/>", 255 + number, number + 2);
+        }
+        let _ = write!(
+            presentation,
+            "</p:sldIdLst><p:sldSz cx=\"{}\" cy=\"{}\"/><p:notesSz cx=\"6858000\" cy=\"9144000\"/></p:presentation>",
+            emu(self.width),
+            emu(self.height)
+        );
+        add("ppt/presentation.xml", presentation.as_bytes())?;
+        let mut presentation_rels = vec![
+            (office_rel("slideMaster"), "slideMasters/slideMaster1.xml".to_string(), false),
+            (office_rel("theme"), "theme/theme1.xml".to_string(), false),
+        ];
+        for number in 1..=slides {
+            presentation_rels.push((office_rel("slide"), format!("slides/slide{}.xml", number), false));
+        }
+        add("ppt/_rels/presentation.xml.rels", relationships(&presentation_rels).as_bytes())?;
+
+        // --- 何も置かないマスター・レイアウトと、最小限のテーマ ---
+        let regular = self.families.get(&FontStyle::Regular).map_or("", String::as_str);
+        add("ppt/slideMasters/slideMaster1.xml", format!("{}<p:sldMaster xmlns:a=\"{}\" xmlns:r=\"{}\" xmlns:p=\"{}\"><p:cSld><p:bg><p:bgRef idx=\"1001\"><a:schemeClr val=\"bg1\"/></p:bgRef></p:bg><p:spTree>{}</p:spTree></p:cSld><p:clrMap bg1=\"lt1\" tx1=\"dk1\" bg2=\"lt2\" tx2=\"dk2\" accent1=\"accent1\" accent2=\"accent2\" accent3=\"accent3\" accent4=\"accent4\" accent5=\"accent5\" accent6=\"accent6\" hlink=\"hlink\" folHlink=\"folHlink\"/><p:sldLayoutIdLst><p:sldLayoutId id=\"2147483649\" r:id=\"rId1\"/></p:sldLayoutIdLst></p:sldMaster>", XML_HEADER, NS_A, NS_R, NS_P, GROUP_PROPERTIES).as_bytes())?;
+        add(
+            "ppt/slideMasters/_rels/slideMaster1.xml.rels",
+            relationships(&[
+                (office_rel("slideLayout"), "../slideLayouts/slideLayout1.xml".to_string(), false),
+                (office_rel("theme"), "../theme/theme1.xml".to_string(), false),
+            ])
+            .as_bytes(),
+        )?;
+        add("ppt/slideLayouts/slideLayout1.xml", format!("{}<p:sldLayout xmlns:a=\"{}\" xmlns:r=\"{}\" xmlns:p=\"{}\" type=\"blank\" preserve=\"1\"><p:cSld name=\"Blank\"><p:spTree>{}</p:spTree></p:cSld><p:clrMapOvr><a:masterClrMapping/></p:clrMapOvr></p:sldLayout>", XML_HEADER, NS_A, NS_R, NS_P, GROUP_PROPERTIES).as_bytes())?;
+        add(
+            "ppt/slideLayouts/_rels/slideLayout1.xml.rels",
+            relationships(&[(office_rel("slideMaster"), "../slideMasters/slideMaster1.xml".to_string(), false)]).as_bytes(),
+        )?;
+        add("ppt/theme/theme1.xml", theme(regular).as_bytes())?;
+
+        // --- スライドと画像 ---
+        for (index, part) in self.slides.iter().enumerate() {
+            add(&format!("ppt/slides/slide{}.xml", index + 1), part.xml.as_bytes())?;
+            add(&format!("ppt/slides/_rels/slide{}.xml.rels", index + 1), relationships(&part.rels).as_bytes())?;
+        }
+        for (path, name, _, _) in &self.media {
+            let bytes = fs::read(path).map_err(|source| SlideError::ImageRead { path: path.clone(), source })?;
+            add(&format!("ppt/media/{}", name), &bytes)?;
+        }
+
+        let cursor = zip.finish().map_err(|e| SlideError::Pptx(e.to_string()))?;
+        Ok(cursor.into_inner())
+    }
+
+    fn write_text_box(&mut self, xml: &mut String, part: &mut SlidePart, id: usize, text: &TextBox) -> Result<(), SlideError> {
+        let _ = write!(xml, "<p:sp><p:nvSpPr><p:cNvPr id=\"{}\" name=\"Text {}\"/><p:cNvSpPr txBox=\"1\"/><p:nvPr/></p:nvSpPr>", id, id);
+        let _ = write!(xml, "<p:spPr>{}<a:prstGeom prst=\"rect\"><a:avLst/></a:prstGeom><a:noFill/></p:spPr>", self.xfrm(text.frame));
+        // 余白は付けず、枠の左上から文字を置く
+        let _ = write!(
+            xml,
+            "<p:txBody><a:bodyPr wrap=\"{}\" lIns=\"0\" tIns=\"0\" rIns=\"0\" bIns=\"0\" rtlCol=\"0\" anchor=\"t\"><a:noAutofit/></a:bodyPr><a:lstStyle/>",
+            if text.wrap { "square" } else { "none" }
+        );
+        let align = match text.align {
+            HAlign::Left => "l",
+            HAlign::Center => "ctr",
+            HAlign::Right => "r",
+            HAlign::Justify => "just",
+        };
+        for paragraph in &text.paragraphs {
+            let _ = write!(xml, "<a:p><a:pPr algn=\"{}\"><a:lnSpc><a:spcPts val=\"{}\"/></a:lnSpc></a:pPr>", align, hundredths(paragraph.line_pitch));
+            for run in &paragraph.runs {
+                let mut attrs = format!("lang=\"ja-JP\" sz=\"{}\"", hundredths(run.size));
+                match run.style {
+                    FontStyle::Bold => attrs.push_str(" b=\"1\""),
+                    FontStyle::Italic => attrs.push_str(" i=\"1\""),
+                    FontStyle::Regular | FontStyle::Mono => {}
+                }
+                if run.underline {
+                    attrs.push_str(" u=\"sng\"");
+                }
+                if run.letter_spacing != 0.0 {
+                    let _ = write!(attrs, " spc=\"{}\"", hundredths(run.letter_spacing));
+                }
+                let typeface = escape(self.families.get(&run.style).map_or("", String::as_str));
+                let link = match &run.link {
+                    Some(LinkTarget::Uri(uri)) => {
+                        let id = rel(part, "hyperlink", uri, true);
+                        format!("<a:hlinkClick r:id=\"{}\"/>", id)
+                    }
+                    Some(LinkTarget::Slide(target)) => {
+                        self.slide_links.push((part.number, *target));
+                        let id = rel(part, "slide", &format!("slide{}.xml", target), false);
+                        format!("<a:hlinkClick r:id=\"{}\" action=\"ppaction://hlinksldjump\"/>", id)
+                    }
+                    None => String::new(),
+                };
+                let _ = write!(
+                    xml,
+                    "<a:r><a:rPr {} dirty=\"0\">{}<a:latin typeface=\"{t}\"/><a:ea typeface=\"{t}\"/><a:cs typeface=\"{t}\"/>{}</a:rPr><a:t>{}</a:t></a:r>",
+                    attrs,
+                    solid_fill(run.color),
+                    link,
+                    escape(&run.text),
+                    t = typeface
+                );
+            }
+            let size = paragraph.runs.first().map_or(paragraph.line_pitch, |run| run.size);
+            let _ = write!(xml, "<a:endParaRPr lang=\"ja-JP\" sz=\"{}\" dirty=\"0\"/></a:p>", hundredths(size));
+        }
+        xml.push_str("</p:txBody></p:sp>");
+        Ok(())
+    }
+
+    fn write_path(&self, xml: &mut String, id: usize, points: &[PathPoint], closed: bool, fill: Option<SlideColor>, stroke: Option<&Stroke>) {
+        let Some(first) = points.first() else { return };
+        // 制御点も含めて囲む範囲を図形の枠にする
+        let (mut left, mut right, mut bottom, mut top) = (first.x, first.x, first.y, first.y);
+        for point in points {
+            (left, right) = (left.min(point.x), right.max(point.x));
+            (bottom, top) = (bottom.min(point.y), top.max(point.y));
+        }
+        let frame = Frame { x: left, y_top: top, width: right - left, height: top - bottom };
+        let (w, h) = (emu(frame.width).max(1), emu(frame.height).max(1));
+        let pt = |point: &PathPoint| format!("<a:pt x=\"{}\" y=\"{}\"/>", emu(point.x - left), emu(top - point.y));
+
+        let mut path = String::new();
+        let mut controls: Vec<&PathPoint> = Vec::new();
+        for (i, point) in points.iter().enumerate() {
+            if i == 0 {
+                let _ = write!(path, "<a:moveTo>{}</a:moveTo>", pt(point));
+            } else if point.bezier {
+                controls.push(point);
+            } else if let [c1, c2] = controls[..] {
+                let _ = write!(path, "<a:cubicBezTo>{}{}{}</a:cubicBezTo>", pt(c1), pt(c2), pt(point));
+                controls.clear();
+            } else {
+                let _ = write!(path, "<a:lnTo>{}</a:lnTo>", pt(point));
+            }
+        }
+        if closed {
+            path.push_str("<a:close/>");
+        }
+
+        let _ = write!(xml, "<p:sp><p:nvSpPr><p:cNvPr id=\"{}\" name=\"Shape {}\"/><p:cNvSpPr/><p:nvPr/></p:nvSpPr><p:spPr>{}", id, id, self.xfrm(frame));
+        let _ = write!(
+            xml,
+            "<a:custGeom><a:avLst/><a:gdLst/><a:ahLst/><a:cxnLst/><a:rect l=\"0\" t=\"0\" r=\"r\" b=\"b\"/><a:pathLst><a:path w=\"{}\" h=\"{}\"{}>{}</a:path></a:pathLst></a:custGeom>",
+            w,
+            h,
+            if closed { "" } else { " fill=\"none\"" },
+            path
+        );
+        match fill {
+            Some(color) if closed => xml.push_str(&solid_fill(color)),
+            _ => xml.push_str("<a:noFill/>"),
+        }
+        match stroke {
+            Some(stroke) => {
+                let _ = write!(xml, "<a:ln w=\"{}\">{}", emu(stroke.width), solid_fill(stroke.color));
+                if !stroke.dash.is_empty() {
+                    // 破線の長さは線幅に対する比率 (1000分の1パーセント) で書く。奇数個なら2回繰り返す (PDFと同じ)
+                    let mut dash = stroke.dash.clone();
+                    if dash.len() % 2 == 1 {
+                        dash.extend(stroke.dash.clone());
+                    }
+                    let ratio = |length: u32| (length as f32 / stroke.width.max(0.01) * 100_000.0).round() as i64;
+                    xml.push_str("<a:custDash>");
+                    for pair in dash.chunks(2) {
+                        let _ = write!(xml, "<a:ds d=\"{}\" sp=\"{}\"/>", ratio(pair[0]), ratio(pair[1]));
+                    }
+                    xml.push_str("</a:custDash>");
+                }
+                xml.push_str("</a:ln>");
+            }
+            None => xml.push_str("<a:ln><a:noFill/></a:ln>"),
+        }
+        xml.push_str("</p:spPr></p:sp>");
+    }
+
+    fn write_picture(&mut self, xml: &mut String, part: &mut SlidePart, id: usize, path: &Path, frame: Frame, fit: ImageFit) -> Result<(), SlideError> {
+        let (name, width_px, height_px) = self.media(path)?;
+        let rel_id = rel(part, "image", &format!("../media/{}", name), false);
+        let drawn = fit_frame(frame, fit, width_px, height_px);
+        // Cover で枠からはみ出す部分は、画像の端を切り取って表す (1000分の1パーセント)
+        let (placed, crop) = if fit == ImageFit::Cover {
+            let ratio = |cut: f32, whole: f32| (cut.max(0.0) / whole * 100_000.0).round() as i64;
+            let crop = format!(
+                "<a:srcRect l=\"{}\" t=\"{}\" r=\"{}\" b=\"{}\"/>",
+                ratio(frame.x - drawn.x, drawn.width),
+                ratio(drawn.y_top - frame.y_top, drawn.height),
+                ratio(drawn.x + drawn.width - frame.x - frame.width, drawn.width),
+                ratio((frame.y_top - frame.height) - (drawn.y_top - drawn.height), drawn.height)
+            );
+            (frame, crop)
+        } else {
+            (drawn, String::new())
+        };
+        let _ = write!(
+            xml,
+            "<p:pic><p:nvPicPr><p:cNvPr id=\"{}\" name=\"Picture {}\"/><p:cNvPicPr><a:picLocks noChangeAspect=\"1\"/></p:cNvPicPr><p:nvPr/></p:nvPicPr>",
+            id, id
+        );
+        let _ = write!(xml, "<p:blipFill><a:blip r:embed=\"{}\"/>{}<a:stretch><a:fillRect/></a:stretch></p:blipFill>", rel_id, crop);
+        let _ = write!(xml, "<p:spPr>{}<a:prstGeom prst=\"rect\"><a:avLst/></a:prstGeom></p:spPr></p:pic>", self.xfrm(placed));
+        Ok(())
+    }
+
+    /// 画像ファイルをパッケージに加える (同じファイルは一度だけ)。パッケージ内の名前と、幅・高さ (px) を返す
+    fn media(&mut self, path: &Path) -> Result<(String, f32, f32), SlideError> {
+        if let Some((_, name, width, height)) = self.media.iter().find(|(other, ..)| other == path) {
+            return Ok((name.clone(), *width, *height));
+        }
+        let bytes = fs::read(path).map_err(|source| SlideError::ImageRead { path: path.to_path_buf(), source })?;
+        let extension = if bytes.starts_with(b"\x89PNG") {
+            "png"
+        } else if bytes.starts_with(&[0xFF, 0xD8]) {
+            "jpeg"
+        } else {
+            return Err(SlideError::ImageDecode { path: path.to_path_buf(), message: "not a PNG or JPEG file".to_string() });
+        };
+        let raw = RawImage::decode_from_bytes(&bytes, &mut Vec::new())
+            .map_err(|message| SlideError::ImageDecode { path: path.to_path_buf(), message })?;
+        let name = format!("image{}.{}", self.media.len() + 1, extension);
+        let (width, height) = (raw.width as f32, raw.height as f32);
+        self.media.push((path.to_path_buf(), name.clone(), width, height));
+        Ok((name, width, height))
+    }
+
+    /// `frame` の位置と大きさ (y座標はページの上端から測る)
+    fn xfrm(&self, frame: Frame) -> String {
+        format!(
+            "<a:xfrm><a:off x=\"{}\" y=\"{}\"/><a:ext cx=\"{}\" cy=\"{}\"/></a:xfrm>",
+            emu(frame.x),
+            emu(self.height - frame.y_top),
+            emu(frame.width.max(0.0)),
+            emu(frame.height.max(0.0))
+        )
+    }
+}
+
+/// 図形を並べるグループ (スライド・マスター・レイアウトで共通) の設定
+const GROUP_PROPERTIES: &str = "<p:nvGrpSpPr><p:cNvPr id=\"1\" name=\"\"/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr><p:grpSpPr><a:xfrm><a:off x=\"0\" y=\"0\"/><a:ext cx=\"0\" cy=\"0\"/><a:chOff x=\"0\" y=\"0\"/><a:chExt cx=\"0\" cy=\"0\"/></a:xfrm></p:grpSpPr>";
+
+/// PowerPoint が読み込める最小限のテーマ。書体だけ通常の文字のフォントにする
+fn theme(typeface: &str) -> String {
+    let typeface = escape(typeface);
+    let colors = [
+        ("dk1", "000000"),
+        ("lt1", "FFFFFF"),
+        ("dk2", "1F1F1F"),
+        ("lt2", "EEEEEE"),
+        ("accent1", "0000CC"),
+        ("accent2", "CC0000"),
+        ("accent3", "00CC00"),
+        ("accent4", "808080"),
+        ("accent5", "333333"),
+        ("accent6", "CCCCCC"),
+        ("hlink", "0000CC"),
+        ("folHlink", "551A8B"),
+    ];
+    let mut xml = String::from(XML_HEADER);
+    let _ = write!(xml, "<a:theme xmlns:a=\"{}\" name=\"slide_generator\"><a:themeElements><a:clrScheme name=\"slide_generator\">", NS_A);
+    for (name, value) in colors {
+        let _ = write!(xml, "<a:{n}><a:srgbClr val=\"{}\"/></a:{n}>", value, n = name);
+    }
+    xml.push_str("</a:clrScheme><a:fontScheme name=\"slide_generator\">");
+    for kind in ["majorFont", "minorFont"] {
+        let _ = write!(xml, "<a:{k}><a:latin typeface=\"{t}\"/><a:ea typeface=\"{t}\"/><a:cs typeface=\"\"/></a:{k}>", k = kind, t = typeface);
+    }
+    let fill = "<a:solidFill><a:schemeClr val=\"phClr\"/></a:solidFill>";
+    let _ = write!(
+        xml,
+        "</a:fontScheme><a:fmtScheme name=\"slide_generator\"><a:fillStyleLst>{f}{f}{f}</a:fillStyleLst><a:lnStyleLst>{l}{l}{l}</a:lnStyleLst><a:effectStyleLst>{e}{e}{e}</a:effectStyleLst><a:bgFillStyleLst>{f}{f}{f}</a:bgFillStyleLst></a:fmtScheme></a:themeElements></a:theme>",
+        f = fill,
+        l = format!("<a:ln w=\"6350\">{}</a:ln>", fill),
+        e = "<a:effectStyle><a:effectLst/></a:effectStyle>"
+    );
+    xml
+}
+
+/// フォントファイルのファミリー名 (英語の名前があればそれを使う)
+fn family_name(font: &LoadedFont) -> Result<String, SlideError> {
+    let face = ttf_parser::Face::parse(&font.parsed.original_bytes, 0).map_err(|_| SlideError::FontParse { path: font.path.clone() })?;
+    let names: Vec<_> = face.names().into_iter().filter(|name| name.is_unicode()).collect();
+    let find = |id: u16| {
+        let candidates = names.iter().filter(|name| name.name_id == id);
+        let english = candidates.clone().find(|name| name.language() == ttf_parser::Language::English_UnitedStates);
+        english.or(candidates.clone().next()).and_then(|name| name.to_string())
+    };
+    // 見つからなければファイル名を使う
+    Ok(find(name_id::TYPOGRAPHIC_FAMILY)
+        .or_else(|| find(name_id::FAMILY))
+        .unwrap_or_else(|| font.path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned())))
+}
+
+/// スライドの関係を追加し、その id を返す (同じ関係が既にあればその id)
+fn rel(part: &mut SlidePart, kind: &str, target: &str, external: bool) -> String {
+    let entry = (office_rel(kind), target.to_string(), external);
+    let index = match part.rels.iter().position(|other| *other == entry) {
+        Some(index) => index,
+        None => {
+            part.rels.push(entry);
+            part.rels.len() - 1
+        }
+    };
+    format!("rId{}", index + 1)
+}
+
+fn office_rel(kind: &str) -> String {
+    format!("{}/{}", NS_R, kind)
+}
+
+/// 関係の一覧 (.rels)。id は並び順に rId1, rId2, ... とする
+fn relationships(rels: &[(String, String, bool)]) -> String {
+    let mut xml = String::from(XML_HEADER);
+    let _ = write!(xml, "<Relationships xmlns=\"{}\">", NS_PACKAGE_RELS);
+    for (index, (kind, target, external)) in rels.iter().enumerate() {
+        let mode = if *external { " TargetMode=\"External\"" } else { "" };
+        let _ = write!(xml, "<Relationship Id=\"rId{}\" Type=\"{}\" Target=\"{}\"{}/>", index + 1, kind, escape(target), mode);
+    }
+    xml.push_str("</Relationships>");
+    xml
+}
+
+fn solid_fill(color: SlideColor) -> String {
+    format!("<a:solidFill><a:srgbClr val=\"{}\"/></a:solidFill>", color.to_hex()[1..].to_ascii_uppercase())
+}
+
+fn emu(pt: f32) -> i64 {
+    (pt * EMU_PER_PT).round() as i64
+}
+
+/// 文字の大きさや間隔は 1/100 pt で書く
+fn hundredths(pt: f32) -> i64 {
+    (pt * 100.0).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::deck::test_deck;
+    use crate::{BlockLayout, Content, FontStyle, Link, LinkTarget, NamedColor, SlideColor, SlideError, TextSpan};
+    use std::collections::HashMap;
+    use std::io::{Cursor, Read};
+    use zip::ZipArchive;
+
+    fn span(text: &str, style: FontStyle, size_ratio: f32, color: SlideColor, link: Option<&str>) -> Content {
+        let link = link.map(|target| Link { target: LinkTarget::parse(target).unwrap(), underline: true });
+        Content::Span(TextSpan { text: text.to_string(), style, size_ratio, color, link })
+    }
+
+    fn read(zip: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+        let mut text = String::new();
+        zip.by_name(name).unwrap().read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn runs_keep_style_size_color_and_links() {
+        let black = SlideColor::Named(NamedColor::Black);
+        let accent = SlideColor::parse("#d0451b", &HashMap::new()).unwrap();
+        let mut deck = test_deck();
+        deck.add_slide().add_text_block(
+            vec![span("Bold", FontStyle::Bold, 1.5, accent, None), span("next", FontStyle::Regular, 1.0, black, Some("#2"))],
+            BlockLayout::at(1.0, 1.0),
+        );
+        deck.add_slide().add_text_block(vec![span("web", FontStyle::Regular, 0.5, black, Some("https://example.com"))], BlockLayout::at(1.0, 1.0));
+
+        let mut zip = ZipArchive::new(Cursor::new(deck.save_to_pptx().unwrap())).unwrap();
+        let types = read(&mut zip, "[Content_Types].xml");
+        assert!(types.contains("PartName=\"/ppt/slides/slide1.xml\""));
+        assert!(types.contains("PartName=\"/ppt/slides/slide2.xml\""));
+        assert!(!types.contains("slide3.xml"));
+
+        // 大きさは 比率 × 24pt を 1/100 pt で書く
+        let slide = read(&mut zip, "ppt/slides/slide1.xml");
+        assert!(slide.contains("<a:rPr lang=\"ja-JP\" sz=\"3600\" b=\"1\" dirty=\"0\"><a:solidFill><a:srgbClr val=\"D0451B\"/>"), "{}", slide);
+        assert!(slide.contains("<a:rPr lang=\"ja-JP\" sz=\"2400\" u=\"sng\" dirty=\"0\"><a:solidFill><a:srgbClr val=\"000000\"/>"), "{}", slide);
+        assert!(slide.contains("<a:hlinkClick r:id=\"rId2\" action=\"ppaction://hlinksldjump\"/>"));
+        let rels = read(&mut zip, "ppt/slides/_rels/slide1.xml.rels");
+        assert!(rels.contains("Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/slide\" Target=\"slide2.xml\"/>"), "{}", rels);
+
+        let slide = read(&mut zip, "ppt/slides/slide2.xml");
+        assert!(slide.contains("sz=\"1200\""));
+        let rels = read(&mut zip, "ppt/slides/_rels/slide2.xml.rels");
+        assert!(rels.contains("relationships/hyperlink\" Target=\"https://example.com\" TargetMode=\"External\"/>"), "{}", rels);
+    }
+
+    #[test]
+    fn link_to_a_missing_slide_is_an_error() {
+        let mut deck = test_deck();
+        let black = SlideColor::Named(NamedColor::Black);
+        deck.add_slide().add_text_block(vec![span("gone", FontStyle::Regular, 1.0, black, Some("#3"))], BlockLayout::at(1.0, 1.0));
+        assert!(matches!(deck.save_to_pptx(), Err(SlideError::BrokenLink { slide: 1, target: 3, slides: 1 })));
+    }
+}
@@ -0,0 +1,333 @@
+// --- PNGへの出力 ---
+//
+// レイアウト済みのページ (page.rs) を、CPUだけで画素に描く (tiny-skia)。
+// 文字は読み込んだTTFのグリフの輪郭をそのまま塗るので、PDFと同じフォント・同じ送り幅になる。
+// 座標はpt単位・原点が左下のまま、描くときの変換で解像度と上下の向きを合わせる。
+
+use crate::error::SlideError;
+use crate::font::LoadedFont;
+use crate::image::fit_frame;
+use crate::page::{page_file_name, DrawOp, Frame, Page, PathPoint, Renderer};
+use crate::{FontStyle, SlideColor};
+use printpdf::{RawImage, RawImageData, RawImageFormat};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tiny_skia::{
+    Color, FillRule, FilterQuality, Mask, Paint, PathBuilder, Pattern, Pixmap, PixmapPaint, Rect, SpreadMode, StrokeDash,
+    Transform,
+};
+use ttf_parser::{Face, GlyphId, OutlineBuilder};
+
+/// `page` 枚目 (1始まり) のスライドのPNGのファイル名 ("slide-01.png" など)
+pub fn png_file_name(page: usize, pages: usize) -> String {
+    page_file_name(page, pages, "png")
+}
+
+/// ページを1枚ずつ画像にしていく
+pub(crate) struct PngRenderer<'a> {
+    faces: HashMap<FontStyle, Face<'a>>,
+    images: HashMap<PathBuf, Pixmap>, // 画像ファイル → デコードした画素
+    scale: f32,                       // 1pt の画素数
+    pixmaps: Vec<Pixmap>,
+}
+
+impl<'a> PngRenderer<'a> {
+    /// `dpi` は1インチ (72pt) あたりの画素数
+    pub(crate) fn new(fonts: &'a HashMap<FontStyle, LoadedFont>, dpi: f32) -> Result<Self, SlideError> {
+        if !(dpi.is_finite() && dpi > 0.0) {
+            return Err(SlideError::Raster(format!("invalid resolution {} dpi", dpi)));
+        }
+        let mut faces = HashMap::new();
+        for (style, font) in fonts {
+            let face = Face::parse(&font.parsed.original_bytes, 0).map_err(|_| SlideError::FontParse { path: font.path.clone() })?;
+            faces.insert(*style, face);
+        }
+        Ok(PngRenderer { faces, images: HashMap::new(), scale: dpi / 72.0, pixmaps: Vec::new() })
+    }
+
+    /// 描いたページの画像 (先頭のページから順に)
+    pub(crate) fn finish(self) -> Vec<Pixmap> {
+        self.pixmaps
+    }
+
+    /// 画像ファイルを画素にする (同じファイルは一度だけ読む)
+    fn image(&mut self, path: &Path) -> Result<&Pixmap, SlideError> {
+        if !self.images.contains_key(path) {
+            let bytes = fs::read(path).map_err(|source| SlideError::ImageRead { path: path.to_path_buf(), source })?;
+            let raw = RawImage::decode_from_bytes(&bytes, &mut Vec::new())
+                .map_err(|message| SlideError::ImageDecode { path: path.to_path_buf(), message })?;
+            let pixmap = raw_to_pixmap(&raw).ok_or_else(|| SlideError::ImageDecode { path: path.to_path_buf(), message: "unsupported pixel format".to_string() })?;
+            self.images.insert(path.to_path_buf(), pixmap);
+        }
+        Ok(&self.images[path])
+    }
+}
+
+impl Renderer for PngRenderer<'_> {
+    fn render_page(&mut self, page: &Page) -> Result<(), SlideError> {
+        let (width, height) = ((page.width * self.scale).round() as u32, (page.height * self.scale).round() as u32);
+        let mut pixmap = Pixmap::new(width, height)
+            .ok_or_else(|| SlideError::Raster(format!("cannot allocate a {}x{} pixel image", width, height)))?;
+        pixmap.fill(Color::WHITE);
+        // pt (原点が左下) → 画素 (原点が左上)
+        let transform = Transform::from_row(self.scale, 0.0, 0.0, -self.scale, 0.0, page.height * self.scale);
+
+        // PushClip ごとに、それまでの範囲と重ねたマスクを積む
+        let mut clips: Vec<Mask> = Vec::new();
+        for op in &page.ops {
+            match op {
+                DrawOp::Text { x, baseline, style, size, color, letter_spacing, text } => {
+                    let face = self.faces.get(style).ok_or(SlideError::UnknownStyle(*style))?;
+                    let units = *size / f32::from(face.units_per_em());
+                    let paint = solid(*color);
+                    let mut pen = *x;
+                    for c in text.chars() {
+                        // cmapに無い文字は .notdef (グリフ0) として描く (PDFと同じ)
+                        let glyph = face.glyph_index(c).unwrap_or(GlyphId(0));
+                        let mut outline = GlyphPath(PathBuilder::new());
+                        if face.outline_glyph(glyph, &mut outline).is_some()
+                            && let Some(path) = outline.0.finish()
+                        {
+                            let glyph_transform = transform.pre_translate(pen, *baseline).pre_scale(units, units);
+                            pixmap.fill_path(&path, &paint, FillRule::Winding, glyph_transform, clips.last());
+                        }
+                        pen += f32::from(face.glyph_hor_advance(glyph).unwrap_or(0)) * units + letter_spacing;
+                    }
+                }
+                DrawOp::Path { points, closed, fill, stroke } => {
+                    let Some(path) = build_path(points, *closed) else { continue };
+                    if let Some(fill) = fill
+                        && *closed
+                    {
+                        pixmap.fill_path(&path, &solid(*fill), FillRule::Winding, transform, clips.last());
+                    }
+                    if let Some(stroke) = stroke {
+                        let mut dash = stroke.dash.iter().map(|&d| d as f32).collect::<Vec<f32>>();
+                        // 奇数個の指定は2回繰り返したものとして扱う (PDFと同じ)
+                        if dash.len() % 2 == 1 {
+                            dash.extend(dash.clone());
+                        }
+                        let style = tiny_skia::Stroke { width: stroke.width, dash: StrokeDash::new(dash, 0.0), ..Default::default() };
+                        pixmap.stroke_path(&path, &solid(stroke.color), &style, transform, clips.last());
+                    }
+                }
+                DrawOp::Image { path, frame, fit } => {
+                    let image = self.image(path)?;
+                    let drawn = fit_frame(*frame, *fit, image.width() as f32, image.height() as f32);
+                    // 画像の左上を drawn の左上に合わせ、画素を pt に縮める
+                    let image_transform = Transform::from_row(
+                        drawn.width / image.width() as f32,
+                        0.0,
+                        0.0,
+                        -drawn.height / image.height() as f32,
+                        drawn.x,
+                        drawn.y_top,
+                    );
+                    let paint = Paint {
+                        shader: Pattern::new(image.as_ref(), SpreadMode::Pad, FilterQuality::Bilinear, 1.0, image_transform),
+                        ..Default::default()
+                    };
+                    // 枠からはみ出した部分 (Cover のとき) は描かない
+                    if let Some(rect) = intersect(drawn, *frame) {
+                        pixmap.fill_rect(rect, &paint, transform, clips.last());
+                    }
+                }
+                DrawOp::PushClip(frame) => {
+                    let mut mask = match clips.last() {
+                        Some(mask) => mask.clone(),
+                        None => {
+                            let mut mask = Mask::new(width, height).ok_or_else(|| SlideError::Raster("cannot allocate a clip mask".to_string()))?;
+                            mask.data_mut().fill(255);
+                            mask
+                        }
+                    };
+                    if let Some(rect) = frame_rect(*frame) {
+                        mask.intersect_path(&PathBuilder::from_rect(rect), FillRule::Winding, true, transform);
+                    }
+                    clips.push(mask);
+                }
+                DrawOp::PopClip => {
+                    clips.pop();
+                }
+                // 画像にはリンクを付けられない
+                DrawOp::Link { .. } => {}
+            }
+        }
+        self.pixmaps.push(pixmap);
+        Ok(())
+    }
+}
+
+/// ページの画像を `columns` 枚ずつ並べた一覧の画像 (コンタクトシート)。`gap` はページの間と周りの余白 (画素)
+pub(crate) fn contact_sheet(pages: &[Pixmap], columns: usize, gap: u32) -> Result<Pixmap, SlideError> {
+    let columns = columns.clamp(1, pages.len().max(1));
+    let rows = pages.len().div_ceil(columns);
+    let cell_width = pages.iter().map(Pixmap::width).max().unwrap_or(0);
+    let cell_height = pages.iter().map(Pixmap::height).max().unwrap_or(0);
+    let width = columns as u32 * (cell_width + gap) + gap;
+    let height = rows as u32 * (cell_height + gap) + gap;
+    let mut sheet = Pixmap::new(width, height)
+        .ok_or_else(|| SlideError::Raster(format!("cannot allocate a {}x{} pixel contact sheet", width, height)))?;
+    sheet.fill(Color::from_rgba8(0x40, 0x40, 0x40, 0xFF));
+    for (index, page) in pages.iter().enumerate() {
+        let (col, row) = ((index % columns) as u32, (index / columns) as u32);
+        let x = gap + col * (cell_width + gap);
+        let y = gap + row * (cell_height + gap);
+        sheet.draw_pixmap(x as i32, y as i32, page.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
+    }
+    Ok(sheet)
+}
+
+/// PNGのバイト列にする
+pub(crate) fn encode_png(pixmap: &Pixmap) -> Result<Vec<u8>, SlideError> {
+    pixmap.encode_png().map_err(|e| SlideError::Raster(e.to_string()))
+}
+
+/// グリフの輪郭 (フォント単位、y軸は上向き) をパスにする
+struct GlyphPath(PathBuilder);
+
+impl OutlineBuilder for GlyphPath {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.move_to(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.line_to(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.0.quad_to(x1, y1, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.0.cubic_to(x1, y1, x2, y2, x, y);
+    }
+
+    fn close(&mut self) {
+        self.0.close();
+    }
+}
+
+fn solid(color: SlideColor) -> Paint<'static> {
+    let (r, g, b) = color.rgb();
+    let mut paint = Paint::default();
+    paint.set_color(Color::from_rgba(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0), 1.0).unwrap_or(Color::BLACK));
+    paint
+}
+
+/// 点の列をパスにする (点が足りなければ None)
+fn build_path(points: &[PathPoint], closed: bool) -> Option<tiny_skia::Path> {
+    let mut builder = PathBuilder::new();
+    let mut controls: Vec<&PathPoint> = Vec::new();
+    for (i, point) in points.iter().enumerate() {
+        if i == 0 {
+            builder.move_to(point.x, point.y);
+        } else if point.bezier {
+            controls.push(point);
+        } else if let [c1, c2] = controls[..] {
+            builder.cubic_to(c1.x, c1.y, c2.x, c2.y, point.x, point.y);
+            controls.clear();
+        } else {
+            builder.line_to(point.x, point.y);
+        }
+    }
+    if closed {
+        builder.close();
+    }
+    builder.finish()
+}
+
+fn frame_rect(frame: Frame) -> Option<Rect> {
+    Rect::from_xywh(frame.x, frame.y_top - frame.height, frame.width, frame.height)
+}
+
+/// 2つの範囲の重なり (重ならなければ None)
+fn intersect(a: Frame, b: Frame) -> Option<Rect> {
+    let left = a.x.max(b.x);
+    let right = (a.x + a.width).min(b.x + b.width);
+    let bottom = (a.y_top - a.height).max(b.y_top - b.height);
+    let top = a.y_top.min(b.y_top);
+    Rect::from_ltrb(left, bottom, right, top)
+}
+
+/// デコードした画像を、アルファを掛けた RGBA の画素にする
+fn raw_to_pixmap(raw: &RawImage) -> Option<Pixmap> {
+    // 16bit と浮動小数点の画素は 8bit に落とす
+    let samples: Vec<u8> = match &raw.pixels {
+        RawImageData::U8(data) => data.clone(),
+        RawImageData::U16(data) => data.iter().map(|v| (v >> 8) as u8).collect(),
+        RawImageData::F32(data) => data.iter().map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8).collect(),
+    };
+    let channels = match raw.data_format {
+        RawImageFormat::R8 | RawImageFormat::R16 => 1,
+        RawImageFormat::RG8 | RawImageFormat::RG16 => 2,
+        RawImageFormat::RGB8 | RawImageFormat::RGB16 | RawImageFormat::BGR8 | RawImageFormat::RGBF32 => 3,
+        RawImageFormat::RGBA8 | RawImageFormat::RGBA16 | RawImageFormat::BGRA8 | RawImageFormat::RGBAF32 => 4,
+    };
+    let bgr = matches!(raw.data_format, RawImageFormat::BGR8 | RawImageFormat::BGRA8);
+    if samples.len() < raw.width * raw.height * channels {
+        return None;
+    }
+    let mut rgba = Vec::with_capacity(raw.width * raw.height * 4);
+    for pixel in samples.chunks_exact(channels).take(raw.width * raw.height) {
+        let (r, g, b, a) = match *pixel {
+            [l] => (l, l, l, 255),
+            [l, a] => (l, l, l, a),
+            [b, g, r] if bgr => (r, g, b, 255),
+            [r, g, b] => (r, g, b, 255),
+            [b, g, r, a] if bgr => (r, g, b, a),
+            [r, g, b, a] => (r, g, b, a),
+            _ => return None,
+        };
+        let premultiply = |v: u8| ((u16::from(v) * u16::from(a) + 127) / 255) as u8;
+        rgba.extend([premultiply(r), premultiply(g), premultiply(b), a]);
+    }
+    Pixmap::from_vec(rgba, tiny_skia::IntSize::from_wh(raw.width as u32, raw.height as u32)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::test_deck;
+    use crate::font::test_fonts;
+    use crate::parser::parse_deck;
+
+    #[test]
+    fn resolution_must_be_positive() {
+        let fonts = test_fonts();
+        for dpi in [0.0, -72.0, f32::NAN, f32::INFINITY] {
+            assert!(matches!(PngRenderer::new(&fonts, dpi), Err(SlideError::Raster(_))), "{}", dpi);
+        }
+        assert!(PngRenderer::new(&fonts, 72.0).is_ok());
+    }
+
+    #[test]
+    fn pages_are_drawn_at_one_pixel_per_point() {
+        let mut deck = test_deck();
+        let slides = parse_deck("@rect col=2 row=3 width=4 height=2 fill=#d0451b\n").unwrap();
+        deck.add_parsed_slides(&slides, Path::new(".")).unwrap();
+        let pngs = deck.save_to_png(72.0).unwrap();
+        assert_eq!(pngs.len(), 1);
+        let pixmap = Pixmap::decode_png(&pngs[0]).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (32 * 24, 18 * 24));
+        // 四角の中 (左上が 48pt, 72pt) は塗りの色、外は白
+        let inside = pixmap.pixel(96, 96).unwrap();
+        assert_eq!((inside.red(), inside.green(), inside.blue()), (0xd0, 0x45, 0x1b));
+        let outside = pixmap.pixel(10, 10).unwrap();
+        assert_eq!((outside.red(), outside.green(), outside.blue()), (255, 255, 255));
+    }
+
+    #[test]
+    fn contact_sheet_columns_are_clamped() {
+        let pages: Vec<Pixmap> = (0..3).map(|_| Pixmap::new(10, 6).unwrap()).collect();
+        let size = |columns: usize| {
+            let sheet = contact_sheet(&pages, columns, 2).unwrap();
+            (sheet.width(), sheet.height())
+        };
+        assert_eq!(size(2), (2 * 12 + 2, 2 * 8 + 2));
+        // 0列は1列、ページより多い列はページの数に揃える
+        assert_eq!(size(0), (12 + 2, 3 * 8 + 2));
+        assert_eq!(size(5), (3 * 12 + 2, 8 + 2));
+    }
+}
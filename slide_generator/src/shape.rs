@@ -0,0 +1,264 @@
+// --- 図形 (長方形、角丸長方形、楕円、線、折れ線、矢印) ---
+//
+// 位置と大きさはテキストと同じグリッド単位で指定する。線の太さと破線の長さだけはpt単位。
+
+use crate::page::{DrawOp, Frame, PathPoint, Stroke};
+use crate::{DrawConfig, NamedColor, SlideColor};
+
+/// ベジェ曲線で円弧を近似するときの制御点の係数 (4 * (√2 - 1) / 3)
+const KAPPA: f32 = 0.552_284_8;
+
+/// 線のどちらの端に矢印の頭を付けるか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrowHeads {
+    None,
+    Start,
+    End,
+    Both,
+}
+
+impl ArrowHeads {
+    pub fn from_name(name: &str) -> Option<ArrowHeads> {
+        match name {
+            "none" => Some(ArrowHeads::None),
+            "start" => Some(ArrowHeads::Start),
+            "end" => Some(ArrowHeads::End),
+            "both" => Some(ArrowHeads::Both),
+            _ => None,
+        }
+    }
+}
+
+/// 図形の形と位置 (単位はグリッド)
+#[derive(Debug, Clone)]
+pub enum ShapeGeometry {
+    /// 左上 (col, row) から幅 width ・高さ height の長方形。radius > 0 なら角を丸める
+    Rect { col: f32, row: f32, width: f32, height: f32, radius: f32 },
+    /// 左上 (col, row) から幅 width ・高さ height の枠に内接する楕円
+    Ellipse { col: f32, row: f32, width: f32, height: f32 },
+    /// 点 (col, row) を順につないだ線。heads で端に矢印の頭を付ける
+    Path { points: Vec<(f32, f32)>, heads: ArrowHeads },
+}
+
+/// スライドに置く図形1つ分
+#[derive(Debug, Clone)]
+pub struct Shape {
+    pub geometry: ShapeGeometry,
+    pub fill: Option<SlideColor>,   // Noneなら塗らない (線の図形では無視される)
+    pub stroke: Option<SlideColor>, // Noneなら枠線を描かない
+    pub stroke_width: f32,          // 線の太さ (pt)
+    pub dash: Vec<u32>,             // 線と隙間の長さ (pt) を交互に最大6個。空なら実線
+}
+
+impl Shape {
+    /// 既定は塗り無し、黒の1ptの実線
+    pub fn new(geometry: ShapeGeometry) -> Self {
+        Shape { geometry, fill: None, stroke: Some(SlideColor::Named(NamedColor::Black)), stroke_width: 1.0, dash: Vec::new() }
+    }
+
+    pub fn rect(col: f32, row: f32, width: f32, height: f32) -> Self {
+        Shape::new(ShapeGeometry::Rect { col, row, width, height, radius: 0.0 })
+    }
+
+    pub fn rounded_rect(col: f32, row: f32, width: f32, height: f32, radius: f32) -> Self {
+        Shape::new(ShapeGeometry::Rect { col, row, width, height, radius })
+    }
+
+    pub fn ellipse(col: f32, row: f32, width: f32, height: f32) -> Self {
+        Shape::new(ShapeGeometry::Ellipse { col, row, width, height })
+    }
+
+    pub fn line(from: (f32, f32), to: (f32, f32)) -> Self {
+        Shape::polyline(vec![from, to])
+    }
+
+    pub fn polyline(points: Vec<(f32, f32)>) -> Self {
+        Shape::new(ShapeGeometry::Path { points, heads: ArrowHeads::None })
+    }
+
+    /// 終点に矢印の頭が付いた線
+    pub fn arrow(points: Vec<(f32, f32)>) -> Self {
+        Shape::new(ShapeGeometry::Path { points, heads: ArrowHeads::End })
+    }
+
+    pub fn with_fill(mut self, color: SlideColor) -> Self {
+        self.fill = Some(color);
+        self
+    }
+
+    pub fn with_stroke(mut self, color: SlideColor, width: f32) -> Self {
+        self.stroke = Some(color);
+        self.stroke_width = width;
+        self
+    }
+
+    pub fn without_stroke(mut self) -> Self {
+        self.stroke = None;
+        self
+    }
+
+    pub fn with_dash(mut self, dash: Vec<u32>) -> Self {
+        self.dash = dash;
+        self
+    }
+
+    /// 線の図形の矢印の頭を変える (他の図形では何もしない)
+    pub fn with_heads(mut self, new_heads: ArrowHeads) -> Self {
+        if let ShapeGeometry::Path { heads, .. } = &mut self.geometry {
+            *heads = new_heads;
+        }
+        self
+    }
+}
+
+/// 図形を描く命令を作る
+pub(crate) fn shape_ops(shape: &Shape, config: &DrawConfig) -> Vec<DrawOp> {
+    let grid = config.base_font_size.0;
+    let page_height = config.page_height_pt.0;
+    // グリッド座標 → ページ左下を原点とするpt座標
+    let to_pt = |col: f32, row: f32| (col * grid, page_height - row * grid);
+    let stroke = shape.stroke.map(|color| Stroke { color, width: shape.stroke_width, dash: shape.dash.clone() });
+
+    match &shape.geometry {
+        ShapeGeometry::Rect { col, row, width, height, radius } => {
+            let (x, y_top) = to_pt(*col, *row);
+            let (w, h) = (width * grid, height * grid);
+            let points = if *radius > 0.0 {
+                rounded_rect_ring(x, y_top, w, h, (radius * grid).min(w / 2.0).min(h / 2.0))
+            } else {
+                rect_ring(x, y_top, w, h)
+            };
+            closed_path(points, shape.fill, stroke)
+        }
+        ShapeGeometry::Ellipse { col, row, width, height } => {
+            let (x, y_top) = to_pt(*col, *row);
+            let (rx, ry) = (width * grid / 2.0, height * grid / 2.0);
+            closed_path(ellipse_ring(x + rx, y_top - ry, rx, ry), shape.fill, stroke)
+        }
+        ShapeGeometry::Path { points, heads } => {
+            // 線は塗りつぶさない。枠線の色が無ければ何も描かない
+            let Some(stroke) = stroke else { return Vec::new() };
+            if points.len() < 2 {
+                return Vec::new();
+            }
+            let mut points: Vec<(f32, f32)> = points.iter().map(|&(col, row)| to_pt(col, row)).collect();
+            let head_length = (shape.stroke_width * 4.0).max(8.0);
+            let mut head_rings: Vec<Vec<PathPoint>> = Vec::new();
+            // 頭の部分まで線を伸ばすと太い線の端がはみ出すので、線は頭の付け根で止める
+            let last = points.len() - 1;
+            if matches!(heads, ArrowHeads::Start | ArrowHeads::Both)
+                && let Some((ring, base)) = arrow_head(points[1], points[0], head_length)
+            {
+                head_rings.push(ring);
+                points[0] = base;
+            }
+            if matches!(heads, ArrowHeads::End | ArrowHeads::Both)
+                && let Some((ring, base)) = arrow_head(points[last - 1], points[last], head_length)
+            {
+                head_rings.push(ring);
+                points[last] = base;
+            }
+
+            let color = stroke.color;
+            let line = points.iter().map(|&(x, y)| path_point(x, y, false)).collect();
+            let mut ops = vec![DrawOp::Path { points: line, closed: false, fill: None, stroke: Some(stroke) }];
+            ops.extend(head_rings.into_iter().map(|points| DrawOp::Path { points, closed: true, fill: Some(color), stroke: None }));
+            ops
+        }
+    }
+}
+
+/// `frame` を `color` で塗りつぶす命令
+pub(crate) fn fill_rect(frame: Frame, color: SlideColor) -> DrawOp {
+    DrawOp::Path { points: rect_ring(frame.x, frame.y_top, frame.width, frame.height), closed: true, fill: Some(color), stroke: None }
+}
+
+/// `frame` の長方形の枠線
+pub(crate) fn stroke_rect(frame: Frame, stroke: Stroke) -> DrawOp {
+    DrawOp::Path { points: rect_ring(frame.x, frame.y_top, frame.width, frame.height), closed: true, fill: None, stroke: Some(stroke) }
+}
+
+/// 塗りも枠線も無ければ何も描かない
+fn closed_path(points: Vec<PathPoint>, fill: Option<SlideColor>, stroke: Option<Stroke>) -> Vec<DrawOp> {
+    if fill.is_none() && stroke.is_none() {
+        return Vec::new();
+    }
+    vec![DrawOp::Path { points, closed: true, fill, stroke }]
+}
+
+fn path_point(x: f32, y: f32, bezier: bool) -> PathPoint {
+    PathPoint { x, y, bezier }
+}
+
+fn rect_ring(x: f32, y_top: f32, w: f32, h: f32) -> Vec<PathPoint> {
+    let (left, right, top, bottom) = (x, x + w, y_top, y_top - h);
+    [(left, top), (right, top), (right, bottom), (left, bottom)].iter().map(|&(x, y)| path_point(x, y, false)).collect()
+}
+
+fn rounded_rect_ring(x: f32, y_top: f32, w: f32, h: f32, r: f32) -> Vec<PathPoint> {
+    let (left, right, top, bottom) = (x, x + w, y_top, y_top - h);
+    let k = r * KAPPA;
+    vec![
+        // 上辺 → 右上の角
+        path_point(left + r, top, false),
+        path_point(right - r, top, false),
+        path_point(right - r + k, top, true),
+        path_point(right, top - r + k, true),
+        path_point(right, top - r, false),
+        // 右辺 → 右下の角
+        path_point(right, bottom + r, false),
+        path_point(right, bottom + r - k, true),
+        path_point(right - r + k, bottom, true),
+        path_point(right - r, bottom, false),
+        // 下辺 → 左下の角
+        path_point(left + r, bottom, false),
+        path_point(left + r - k, bottom, true),
+        path_point(left, bottom + r - k, true),
+        path_point(left, bottom + r, false),
+        // 左辺 → 左上の角
+        path_point(left, top - r, false),
+        path_point(left, top - r + k, true),
+        path_point(left + r - k, top, true),
+        path_point(left + r, top, false),
+    ]
+}
+
+/// 中心 (cx, cy) ・半径 rx, ry の楕円を、4本のベジェ曲線で近似する
+fn ellipse_ring(cx: f32, cy: f32, rx: f32, ry: f32) -> Vec<PathPoint> {
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+    vec![
+        path_point(cx + rx, cy, false),
+        path_point(cx + rx, cy + ky, true),
+        path_point(cx + kx, cy + ry, true),
+        path_point(cx, cy + ry, false),
+        path_point(cx - kx, cy + ry, true),
+        path_point(cx - rx, cy + ky, true),
+        path_point(cx - rx, cy, false),
+        path_point(cx - rx, cy - ky, true),
+        path_point(cx - kx, cy - ry, true),
+        path_point(cx, cy - ry, false),
+        path_point(cx + kx, cy - ry, true),
+        path_point(cx + rx, cy - ky, true),
+        path_point(cx + rx, cy, false),
+    ]
+}
+
+/// from → tip の向きの矢印の頭 (三角形) と、その付け根の中心を返す (向きが決まらなければNone)
+fn arrow_head(from: (f32, f32), tip: (f32, f32), length: f32) -> Option<(Vec<PathPoint>, (f32, f32))> {
+    let (dx, dy) = (tip.0 - from.0, tip.1 - from.1);
+    let segment = (dx * dx + dy * dy).sqrt();
+    if segment == 0.0 {
+        return None;
+    }
+    // 線分より長い頭は付けない
+    let length = length.min(segment);
+    let (ux, uy) = (dx / segment, dy / segment);
+    let base = (tip.0 - ux * length, tip.1 - uy * length);
+    let half = length / 2.0;
+    let ring = vec![
+        path_point(tip.0, tip.1, false),
+        path_point(base.0 - uy * half, base.1 + ux * half, false),
+        path_point(base.0 + uy * half, base.1 - ux * half, false),
+    ];
+    Some((ring, base))
+}
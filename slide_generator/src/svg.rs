@@ -0,0 +1,340 @@
+// --- SVGへの出力 ---
+//
+// レイアウト済みのページ (page.rs) を、1ページ = 1つのSVG文書にする。
+// 文字は <text> のまま (フォントは @font-face で埋め込むか参照する)、図形は <path>、
+// 画像は <image> (データURIで埋め込む)、リンクは <a> で囲んだ透明な <rect> にする。
+// SVGのy座標はページの上端から下向きに測るので、ここで向きを直す。
+
+use crate::error::SlideError;
+use crate::font::{font_for, LoadedFont};
+use crate::page::{page_file_name, DrawOp, Frame, Page, PathPoint, Renderer};
+use crate::{FontStyle, ImageFit, LinkTarget};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// SVGで使うフォントの渡し方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SvgFonts {
+    /// ページで使う文字だけを取り出したフォントを、データURIで埋め込む
+    #[default]
+    Embed,
+    /// フォントファイルのパスをそのまま参照する (SVGを置く場所から見えるパスにしておく)
+    Link,
+}
+
+impl SvgFonts {
+    pub fn from_name(name: &str) -> Option<SvgFonts> {
+        match name {
+            "embed" => Some(SvgFonts::Embed),
+            "link" => Some(SvgFonts::Link),
+            _ => None,
+        }
+    }
+}
+
+/// `page` 枚目 (1始まり) のスライドのSVGのファイル名 ("slide-01.svg" など)。
+/// スライドへのリンクもこの名前を指すので、出力するときはこの名前で同じディレクトリに置く
+pub fn svg_file_name(page: usize, pages: usize) -> String {
+    page_file_name(page, pages, "svg")
+}
+
+/// フォントの登録順。同じファイルを使うスタイルは、先に出てくるスタイルのフォントとしてまとめる
+const FONT_STYLES: [FontStyle; 4] = [FontStyle::Regular, FontStyle::Bold, FontStyle::Italic, FontStyle::Mono];
+
+/// フォント (まとめた先のスタイル) ごとに、使われている文字
+pub(crate) type UsedChars = Vec<(FontStyle, BTreeSet<char>)>;
+
+/// ページを <svg> 要素にする。SVGファイルとHTMLの両方で使う
+pub(crate) struct SvgWriter<'a> {
+    fonts: &'a HashMap<FontStyle, LoadedFont>,
+    images: HashMap<PathBuf, String>, // 画像ファイル → データURI
+    written: usize,                   // 書いたページ数 (クリップのidをページ間で重ねないため)
+}
+
+impl<'a> SvgWriter<'a> {
+    pub(crate) fn new(fonts: &'a HashMap<FontStyle, LoadedFont>) -> Self {
+        SvgWriter { fonts, images: HashMap::new(), written: 0 }
+    }
+
+    /// `style` のフォントをまとめる先のスタイル (@font-face の font-family 名はこれで決める)
+    fn family(&self, style: FontStyle) -> Result<FontStyle, SlideError> {
+        let path = &font_for(self.fonts, style)?.path;
+        Ok(FONT_STYLES.into_iter().find(|other| self.fonts.get(other).is_some_and(|font| &font.path == path)).unwrap_or(style))
+    }
+
+    /// ページで使われている文字を `used` に加える
+    pub(crate) fn collect_chars(&self, page: &Page, used: &mut UsedChars) -> Result<(), SlideError> {
+        for op in &page.ops {
+            if let DrawOp::Text { style, text, .. } = op {
+                let family = self.family(*style)?;
+                match used.iter_mut().find(|(other, _)| *other == family) {
+                    Some((_, chars)) => chars.extend(text.chars()),
+                    None => used.push((family, text.chars().collect())),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `used` のフォントの @font-face
+    pub(crate) fn font_faces(&self, used: &UsedChars, mode: SvgFonts) -> Result<String, SlideError> {
+        let mut css = String::new();
+        for (family, chars) in used {
+            let font = font_for(self.fonts, *family)?;
+            let src = match mode {
+                // 取り出せなければフォント全体を埋め込む
+                SvgFonts::Embed => {
+                    let bytes = font.parsed.subset_simple(chars).map(|subset| subset.bytes).unwrap_or_else(|_| font.parsed.original_bytes.clone());
+                    format!("url(\"data:font/ttf;base64,{}\")", BASE64.encode(bytes))
+                }
+                SvgFonts::Link => format!("url(\"{}\")", escape(&font.path.to_string_lossy())),
+            };
+            let _ = writeln!(css, "@font-face {{ font-family: \"{}\"; src: {}; }}", family_name(*family), src);
+        }
+        Ok(css)
+    }
+
+    /// 画像ファイルをデータURIにする (同じファイルは一度だけ読む)
+    fn image_uri(&mut self, path: &Path) -> Result<String, SlideError> {
+        if let Some(uri) = self.images.get(path) {
+            return Ok(uri.clone());
+        }
+        let bytes = fs::read(path).map_err(|source| SlideError::ImageRead { path: path.to_path_buf(), source })?;
+        let mime = if bytes.starts_with(b"\x89PNG") {
+            "image/png"
+        } else if bytes.starts_with(&[0xFF, 0xD8]) {
+            "image/jpeg"
+        } else {
+            return Err(SlideError::ImageDecode { path: path.to_path_buf(), message: "not a PNG or JPEG file".to_string() });
+        };
+        let uri = format!("data:{};base64,{}", mime, BASE64.encode(bytes));
+        self.images.insert(path.to_path_buf(), uri.clone());
+        Ok(uri)
+    }
+
+    /// ページを1つの <svg> 要素にする。`style` は <style> の中身、`slide_href` はスライド番号 (1始まり) からリンク先を作る
+    pub(crate) fn svg_element(&mut self, page: &Page, style: &str, slide_href: impl Fn(usize) -> String) -> Result<String, SlideError> {
+        self.written += 1;
+        let height = page.height;
+        // y座標をページ上端からの距離に直す
+        let flip = |y: f32| num(height - y);
+        let rect = |frame: &Frame| {
+            format!("x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"", num(frame.x), flip(frame.y_top), num(frame.width), num(frame.height))
+        };
+
+        let mut body = String::new();
+        let mut clips = 0;
+        for op in &page.ops {
+            match op {
+                DrawOp::Text { x, baseline, style, size, color, letter_spacing, text } => {
+                    let spacing = if *letter_spacing == 0.0 { String::new() } else { format!(" letter-spacing=\"{}\"", num(*letter_spacing)) };
+                    let _ = writeln!(
+                        body,
+                        "<text x=\"{}\" y=\"{}\" font-family=\"{}\" font-size=\"{}\" fill=\"{}\"{}>{}</text>",
+                        num(*x),
+                        flip(*baseline),
+                        family_name(self.family(*style)?),
+                        num(*size),
+                        color.to_hex(),
+                        spacing,
+                        escape(text)
+                    );
+                }
+                DrawOp::Path { points, closed, fill, stroke } => {
+                    let fill = fill.map_or_else(|| "none".to_string(), |color| color.to_hex());
+                    let mut attrs = format!("fill=\"{}\"", fill);
+                    if let Some(stroke) = stroke {
+                        let _ = write!(attrs, " stroke=\"{}\" stroke-width=\"{}\"", stroke.color.to_hex(), num(stroke.width));
+                        if !stroke.dash.is_empty() {
+                            let dash: Vec<String> = stroke.dash.iter().map(u32::to_string).collect();
+                            let _ = write!(attrs, " stroke-dasharray=\"{}\"", dash.join(" "));
+                        }
+                    }
+                    let _ = writeln!(body, "<path d=\"{}\" {}/>", path_data(points, *closed, height), attrs);
+                }
+                DrawOp::Image { path, frame, fit } => {
+                    let aspect = match fit {
+                        ImageFit::Contain => "xMidYMid meet",
+                        ImageFit::Cover => "xMidYMid slice",
+                        ImageFit::Stretch => "none",
+                    };
+                    let uri = self.image_uri(path)?;
+                    let _ = writeln!(body, "<image {} preserveAspectRatio=\"{}\" href=\"{}\"/>", rect(frame), aspect, uri);
+                }
+                DrawOp::PushClip(frame) => {
+                    clips += 1;
+                    let id = format!("clip-{}-{}", self.written, clips);
+                    let _ = writeln!(body, "<clipPath id=\"{}\"><rect {}/></clipPath>", id, rect(frame));
+                    let _ = writeln!(body, "<g clip-path=\"url(#{})\">", id);
+                }
+                DrawOp::PopClip => body.push_str("</g>\n"),
+                DrawOp::Link { frame, target } => {
+                    let href = match target {
+                        LinkTarget::Uri(uri) => uri.clone(),
+                        LinkTarget::Slide(page) => slide_href(*page),
+                    };
+                    let _ = writeln!(body, "<a href=\"{}\"><rect {} fill=\"transparent\"/></a>", escape(&href), rect(frame));
+                }
+            }
+        }
+
+        let (width, height) = (num(page.width), num(page.height));
+        let mut element = String::new();
+        let _ = writeln!(
+            element,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" xml:space=\"preserve\">",
+            width, height, width, height
+        );
+        if !style.is_empty() {
+            let _ = writeln!(element, "<style>\n{}</style>", style);
+        }
+        element.push_str(&body);
+        element.push_str("</svg>\n");
+        Ok(element)
+    }
+}
+
+/// ページを1枚ずつSVG文書にしていく
+pub(crate) struct SvgRenderer<'a> {
+    writer: SvgWriter<'a>,
+    mode: SvgFonts,
+    pages: usize,
+    documents: Vec<String>,
+}
+
+impl<'a> SvgRenderer<'a> {
+    /// `pages` はデッキの総ページ数 (スライドへのリンクのファイル名に使う)
+    pub(crate) fn new(fonts: &'a HashMap<FontStyle, LoadedFont>, mode: SvgFonts, pages: usize) -> Self {
+        SvgRenderer { writer: SvgWriter::new(fonts), mode, pages, documents: Vec::new() }
+    }
+
+    /// 描いたページのSVG文書 (先頭のページから順に)
+    pub(crate) fn finish(self) -> Vec<String> {
+        self.documents
+    }
+}
+
+impl Renderer for SvgRenderer<'_> {
+    fn render_page(&mut self, page: &Page) -> Result<(), SlideError> {
+        // フォントはページごとに、そのページで使う文字だけを埋め込む
+        let mut used = UsedChars::new();
+        self.writer.collect_chars(page, &mut used)?;
+        let style = self.writer.font_faces(&used, self.mode)?;
+        let pages = self.pages;
+        let document = self.writer.svg_element(page, &style, |slide| svg_file_name(slide, pages))?;
+        self.documents.push(document);
+        Ok(())
+    }
+}
+
+fn family_name(style: FontStyle) -> String {
+    format!("slide-{}", format!("{:?}", style).to_ascii_lowercase())
+}
+
+/// 点の列を <path> の d 属性にする
+fn path_data(points: &[PathPoint], closed: bool, height: f32) -> String {
+    let mut data = String::new();
+    let mut controls: Vec<&PathPoint> = Vec::new();
+    for (i, point) in points.iter().enumerate() {
+        let xy = format!("{} {}", num(point.x), num(height - point.y));
+        if i == 0 {
+            data.push_str(&format!("M{}", xy));
+        } else if point.bezier {
+            controls.push(point);
+        } else if let [c1, c2] = controls[..] {
+            data.push_str(&format!(" C{} {} {} {} {}", num(c1.x), num(height - c1.y), num(c2.x), num(height - c2.y), xy));
+            controls.clear();
+        } else {
+            data.push_str(&format!(" L{}", xy));
+        }
+    }
+    if closed {
+        data.push_str(" Z");
+    }
+    data
+}
+
+/// 小数点以下2桁までの数値 (末尾の0は付けない)
+fn num(value: f32) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_string() } else { text.to_string() }
+}
+
+/// テキストや属性値に使えない文字を実体参照にする
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::test_fonts;
+    use crate::{NamedColor, SlideColor};
+
+    fn point(x: f32, y: f32, bezier: bool) -> PathPoint {
+        PathPoint { x, y, bezier }
+    }
+
+    #[test]
+    fn numbers_drop_trailing_zeros() {
+        assert_eq!(num(1.0), "1");
+        assert_eq!(num(100.0), "100");
+        assert_eq!(num(1.5), "1.5");
+        assert_eq!(num(12.3456), "12.35");
+        assert_eq!(num(0.0), "0");
+        // 丸めて 0 になる負の数も "-0" にしない
+        assert_eq!(num(-0.001), "0");
+        assert_eq!(num(-2.5), "-2.5");
+    }
+
+    #[test]
+    fn escapes_markup_characters() {
+        assert_eq!(escape(r#"a<b & "c">"#), "a&lt;b &amp; &quot;c&quot;&gt;");
+        assert_eq!(escape("日本語"), "日本語");
+    }
+
+    #[test]
+    fn path_data_mixes_lines_and_curves() {
+        // 高さ100のページで、y は上端からの距離に直る
+        let points = [point(0.0, 0.0, false), point(10.0, 0.0, false), point(12.0, 5.0, true), point(14.0, 5.0, true), point(20.0, 0.0, false)];
+        assert_eq!(path_data(&points, true, 100.0), "M0 100 L10 100 C12 95 14 95 20 100 Z");
+        assert_eq!(path_data(&points[..2], false, 100.0), "M0 100 L10 100");
+    }
+
+    #[test]
+    fn elements_flip_y_and_number_clips_per_page() {
+        let fonts = test_fonts();
+        let mut writer = SvgWriter::new(&fonts);
+        let frame = Frame { x: 10.0, y_top: 400.0, width: 20.0, height: 30.0 };
+        let page = Page {
+            width: 768.0,
+            height: 432.0,
+            ops: vec![
+                DrawOp::PushClip(frame),
+                DrawOp::Text {
+                    x: 48.0,
+                    baseline: 336.0,
+                    style: FontStyle::Regular,
+                    size: 24.0,
+                    color: SlideColor::Named(NamedColor::Black),
+                    letter_spacing: 0.0,
+                    text: "a&b".to_string(),
+                },
+                DrawOp::PopClip,
+            ],
+        };
+        let first = writer.svg_element(&page, "", |slide| format!("#{}", slide)).unwrap();
+        let second = writer.svg_element(&page, "", |slide| format!("#{}", slide)).unwrap();
+        assert!(first.contains("<text x=\"48\" y=\"96\" font-family=\"slide-regular\" font-size=\"24\" fill=\"#000000\">a&amp;b</text>"), "{}", first);
+        assert!(first.contains("<clipPath id=\"clip-1-1\"><rect x=\"10\" y=\"32\" width=\"20\" height=\"30\"/></clipPath>"), "{}", first);
+        assert!(first.contains("<g clip-path=\"url(#clip-1-1)\">"));
+        // 同じHTMLに並べてもidが重ならない
+        assert!(second.contains("<clipPath id=\"clip-2-1\">"));
+        assert!(!second.contains("clip-1-1"));
+    }
+}
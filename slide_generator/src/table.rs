@@ -0,0 +1,313 @@
+// --- 表 ---
+//
+// セルの中身は draw_text_block で描き、列幅に収まらなければ折り返す。
+// 描く順番は 塗り (見出し行・縞模様) → セルのテキスト → 罫線。
+
+use crate::error::SlideError;
+use crate::font::LoadedFont;
+use crate::page::DrawOp;
+use crate::shape::shape_ops;
+use crate::text::{draw_text_block, span_width};
+use crate::{BlockLayout, Content, DrawConfig, FontStyle, HAlign, NamedColor, Shape, SlideColor, VAlign};
+use std::collections::HashMap;
+
+/// 列の幅の決め方
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnWidth {
+    Fixed(f32), // グリッド単位の固定幅 (セルの余白を含む)
+    Auto,       // 中身に合わせる。表全体が幅に収まらなければ、広い列から順に狭めて折り返す
+}
+
+/// 表のセル1つ分
+#[derive(Debug, Clone)]
+pub struct TableCell {
+    pub contents: Vec<Content>,
+    pub h_align: Option<HAlign>, // Noneなら表の配置 (BlockLayout::h_align) に従う
+    pub v_align: VAlign,         // セル内の縦位置 (Top: 上詰め、Middle: 中央、Bottom: 下詰め)
+}
+
+impl TableCell {
+    pub fn new(contents: Vec<Content>) -> Self {
+        TableCell { contents, h_align: None, v_align: VAlign::Top }
+    }
+
+    pub fn with_h_align(mut self, h_align: HAlign) -> Self {
+        self.h_align = Some(h_align);
+        self
+    }
+
+    pub fn with_v_align(mut self, v_align: VAlign) -> Self {
+        self.v_align = v_align;
+        self
+    }
+}
+
+/// 表の見た目
+#[derive(Debug, Clone)]
+pub struct TableStyle {
+    pub padding: f32,                    // セルの内側の余白 (グリッド単位)
+    pub border: Option<SlideColor>,      // Noneなら罫線を引かない
+    pub border_width: f32,               // 罫線の太さ (pt)
+    pub header_fill: Option<SlideColor>, // 見出し行の背景色
+    pub header_bold: bool,               // 見出し行の文字を太字にする
+    pub stripe_fill: Option<SlideColor>, // 見出し以外の偶数行 (2, 4, ...行目) の背景色
+}
+
+impl Default for TableStyle {
+    fn default() -> Self {
+        TableStyle {
+            padding: 0.3,
+            border: Some(SlideColor::Named(NamedColor::Black)),
+            border_width: 1.0,
+            header_fill: Some(SlideColor::Custom(0.85, 0.85, 0.85)),
+            header_bold: true,
+            stripe_fill: None,
+        }
+    }
+}
+
+/// 表1つ分。行ごとにセルを並べる (セルが足りない行は空のセルで埋める)
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub rows: Vec<Vec<TableCell>>,
+    pub columns: Vec<ColumnWidth>, // 指定の無い列は Auto
+    pub header: bool,              // 先頭行を見出し行にする
+    pub style: TableStyle,
+}
+
+impl Table {
+    pub fn new(rows: Vec<Vec<TableCell>>) -> Self {
+        Table { rows, columns: Vec::new(), header: false, style: TableStyle::default() }
+    }
+
+    pub fn with_columns(mut self, columns: Vec<ColumnWidth>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn with_style(mut self, style: TableStyle) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+/// 表を `layout` の位置 (表の左上) から描画し、次に使える行の位置を返す。
+/// `layout.width` があれば、表全体がその幅に収まるように Auto の列を狭める
+pub(crate) fn draw_table(
+    ops: &mut Vec<DrawOp>,
+    fonts: &HashMap<FontStyle, LoadedFont>,
+    config: &DrawConfig,
+    table: &Table,
+    layout: &BlockLayout,
+) -> Result<f32, SlideError> {
+    let column_count = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+    if column_count == 0 {
+        return Ok(layout.start_row);
+    }
+    let style = &table.style;
+    let padding = style.padding;
+    let empty = TableCell::new(Vec::new());
+
+    // 見出し行の文字は太字にしておく
+    let rows: Vec<Vec<TableCell>> = table
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(row_index, row)| {
+            (0..column_count)
+                .map(|col| {
+                    let mut cell = row.get(col).unwrap_or(&empty).clone();
+                    if row_index == 0 && table.header && style.header_bold {
+                        for content in &mut cell.contents {
+                            if let Content::Span(span) = content {
+                                span.style = FontStyle::Bold;
+                            }
+                        }
+                    }
+                    cell
+                })
+                .collect()
+        })
+        .collect();
+
+    // --- 1. 列幅を決める ---
+    let mut natural = vec![0.0f32; column_count];
+    for row in &rows {
+        for (col, cell) in row.iter().enumerate() {
+            natural[col] = natural[col].max(natural_width(fonts, &cell.contents)? + padding * 2.0);
+        }
+    }
+    let columns: Vec<ColumnWidth> = (0..column_count).map(|col| table.columns.get(col).copied().unwrap_or(ColumnWidth::Auto)).collect();
+    let widths = column_widths(&columns, &natural, layout.width);
+
+    // --- 2. 行の高さを決める (描画せずに測るため、命令は捨てる) ---
+    let mut heights: Vec<Vec<f32>> = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let mut cell_heights = Vec::with_capacity(column_count);
+        for (cell, width) in row.iter().zip(&widths) {
+            let cell_layout = cell_layout(layout, cell, 0.0, 0.0, *width, padding);
+            let height = draw_text_block(&mut Vec::new(), fonts, config, &cell.contents, &cell_layout)? - padding;
+            // 空のセルも1行分の高さを取る
+            cell_heights.push(height.max(layout.line_spacing_ratio));
+        }
+        heights.push(cell_heights);
+    }
+    let row_heights: Vec<f32> = heights.iter().map(|cells| cells.iter().fold(0.0, |a: f32, &b| a.max(b)) + padding * 2.0).collect();
+    let table_width: f32 = widths.iter().sum();
+
+    // --- 3. 塗り ---
+    let mut y = layout.start_row;
+    for (row_index, height) in row_heights.iter().enumerate() {
+        let fill = if row_index == 0 && table.header {
+            style.header_fill
+        } else {
+            let body_index = if table.header { row_index - 1 } else { row_index };
+            style.stripe_fill.filter(|_| body_index % 2 == 1)
+        };
+        if let Some(fill) = fill {
+            ops.extend(shape_ops(&Shape::rect(layout.start_col, y, table_width, *height).with_fill(fill).without_stroke(), config));
+        }
+        y += height;
+    }
+    let table_bottom = y;
+
+    // --- 4. セルのテキスト ---
+    let mut y = layout.start_row;
+    for ((row, cell_heights), row_height) in rows.iter().zip(&heights).zip(&row_heights) {
+        let mut x = layout.start_col;
+        for ((cell, width), cell_height) in row.iter().zip(&widths).zip(cell_heights) {
+            let free = row_height - padding * 2.0 - cell_height;
+            let y_offset = match cell.v_align {
+                VAlign::Top => 0.0,
+                VAlign::Middle => free / 2.0,
+                VAlign::Bottom => free,
+            };
+            draw_text_block(ops, fonts, config, &cell.contents, &cell_layout(layout, cell, x, y + y_offset, *width, padding))?;
+            x += width;
+        }
+        y += row_height;
+    }
+
+    // --- 5. 罫線 (外枠と、すべての行・列の境目) ---
+    if let Some(border) = style.border {
+        let line = |from: (f32, f32), to: (f32, f32)| shape_ops(&Shape::line(from, to).with_stroke(border, style.border_width), config);
+        let (left, right) = (layout.start_col, layout.start_col + table_width);
+        let mut y = layout.start_row;
+        ops.extend(line((left, y), (right, y)));
+        for height in &row_heights {
+            y += height;
+            ops.extend(line((left, y), (right, y)));
+        }
+        let mut x = left;
+        ops.extend(line((x, layout.start_row), (x, table_bottom)));
+        for width in &widths {
+            x += width;
+            ops.extend(line((x, layout.start_row), (x, table_bottom)));
+        }
+    }
+
+    // 表の下には、行間と同じだけの空きを取る
+    Ok(table_bottom + (layout.line_spacing_ratio - 1.0).max(0.0))
+}
+
+/// セルのテキストを描く配置 (セルの左上 (x, y) から余白の分だけ内側)
+fn cell_layout(layout: &BlockLayout, cell: &TableCell, x: f32, y: f32, width: f32, padding: f32) -> BlockLayout {
+    BlockLayout {
+        start_col: x + padding,
+        start_row: y + padding,
+        width: Some((width - padding * 2.0).max(0.0)),
+        h_align: cell.h_align.unwrap_or(layout.h_align),
+        ..*layout
+    }
+}
+
+/// 折り返さずに並べたときの幅 (明示的な改行で区切った行のうち、一番長いもの)
+fn natural_width(fonts: &HashMap<FontStyle, LoadedFont>, contents: &[Content]) -> Result<f32, SlideError> {
+    let mut widest = 0.0f32;
+    let mut line_width = 0.0;
+    for content in contents {
+        match content {
+            Content::Span(span) => line_width += span_width(fonts, span)?,
+            Content::Newline => {
+                widest = widest.max(line_width);
+                line_width = 0.0;
+            }
+        }
+    }
+    Ok(widest.max(line_width))
+}
+
+/// 各列の幅を決める。Auto の列は中身の幅を使うが、表全体が `available` を超える場合は、
+/// 狭い列はそのままにして、残りの幅を広い列で等分する
+fn column_widths(columns: &[ColumnWidth], natural: &[f32], available: Option<f32>) -> Vec<f32> {
+    let mut widths: Vec<f32> = columns
+        .iter()
+        .zip(natural)
+        .map(|(column, natural)| match column {
+            ColumnWidth::Fixed(width) => *width,
+            ColumnWidth::Auto => *natural,
+        })
+        .collect();
+    let Some(available) = available else { return widths };
+    if widths.iter().sum::<f32>() <= available {
+        return widths;
+    }
+
+    let fixed: f32 = columns.iter().filter_map(|column| if let ColumnWidth::Fixed(width) = column { Some(*width) } else { None }).sum();
+    let mut remaining = (available - fixed).max(0.0);
+    let mut unresolved: Vec<usize> = (0..columns.len()).filter(|&col| columns[col] == ColumnWidth::Auto).collect();
+    loop {
+        if unresolved.is_empty() {
+            break;
+        }
+        let share = remaining / unresolved.len() as f32;
+        let (narrow, wide): (Vec<usize>, Vec<usize>) = unresolved.iter().partition(|&&col| natural[col] <= share);
+        if narrow.is_empty() {
+            for col in wide {
+                widths[col] = share;
+            }
+            break;
+        }
+        for col in narrow {
+            remaining -= natural[col];
+        }
+        unresolved = wide;
+    }
+    widths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ColumnWidth::{Auto, Fixed};
+
+    #[test]
+    fn natural_widths_without_a_limit() {
+        assert_eq!(column_widths(&[Auto, Fixed(6.0), Auto], &[2.0, 1.0, 30.0], None), [2.0, 6.0, 30.0]);
+    }
+
+    #[test]
+    fn tables_that_fit_keep_their_widths() {
+        assert_eq!(column_widths(&[Auto, Auto], &[4.0, 6.0], Some(10.0)), [4.0, 6.0]);
+        assert_eq!(column_widths(&[Fixed(3.0), Auto], &[9.0, 6.0], Some(20.0)), [3.0, 6.0]);
+    }
+
+    #[test]
+    fn narrow_columns_keep_their_width_and_wide_ones_share_the_rest() {
+        // 3等分 (8) より狭い列はそのまま残し、残りの 22 を広い2列で等分する
+        assert_eq!(column_widths(&[Auto, Auto, Auto], &[2.0, 12.0, 30.0], Some(24.0)), [2.0, 11.0, 11.0]);
+        // 等分した幅より狭い列が、2回目の等分で初めて見つかる場合
+        assert_eq!(column_widths(&[Auto, Auto, Auto], &[7.0, 9.0, 30.0], Some(24.0)), [7.0, 8.5, 8.5]);
+    }
+
+    #[test]
+    fn fixed_columns_are_never_shrunk() {
+        assert_eq!(column_widths(&[Fixed(6.0), Auto, Auto], &[1.0, 10.0, 10.0], Some(14.0)), [6.0, 4.0, 4.0]);
+        assert_eq!(column_widths(&[Fixed(10.0), Auto], &[1.0, 5.0], Some(8.0)), [10.0, 0.0]);
+    }
+}
@@ -0,0 +1,450 @@
+use crate::error::SlideError;
+use crate::font::{font_for, LoadedFont};
+use crate::linebreak;
+use crate::page::{DrawOp, Frame};
+use crate::shape::fill_rect;
+use crate::{Background, NamedColor, SlideColor};
+use printpdf::Pt;
+use std::collections::HashMap;
+
+// --- 型定義 ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontStyle { Regular, Bold, Italic, Mono /* 等幅 (コードブロック用) */ }
+
+/// ページの大きさと、文字・背景の既定スタイル
+#[derive(Debug, Clone)]
+pub struct DrawConfig {
+    pub page_width_pt: Pt,
+    pub page_height_pt: Pt,
+    pub base_font_size: Pt, // 1グリッドの大きさでもある
+    pub default_font_style: FontStyle,
+    pub default_color: SlideColor,
+    pub palette: HashMap<String, SlideColor>, // マークアップで使える追加の色名 (小文字)
+    pub background: Background,               // スライドごとに指定が無いときの背景
+    pub title_size_ratio: f32,
+    pub subtitle_size_ratio: f32,
+    pub body_size_ratio: f32,
+    pub line_spacing_ratio: f32, // 本文の行送り
+    pub link_color: Option<SlideColor>, // マークアップのリンクの文字色 (Noneなら周りの文字と同じ)
+    pub link_underline: bool,           // マークアップのリンクに下線を引くか
+}
+
+impl DrawConfig {
+    /// 基準フォントサイズ (pt) とグリッド数からページを決める
+    pub fn from_grid(base_font_size: f32, grid_cols: f32, grid_rows: f32) -> Self {
+        DrawConfig {
+            page_width_pt: Pt(grid_cols * base_font_size),
+            page_height_pt: Pt(grid_rows * base_font_size),
+            base_font_size: Pt(base_font_size),
+            default_font_style: FontStyle::Regular,
+            default_color: SlideColor::Named(NamedColor::Black),
+            palette: HashMap::new(),
+            background: Background::default(),
+            title_size_ratio: 2.0,
+            subtitle_size_ratio: 1.5,
+            body_size_ratio: 1.0,
+            line_spacing_ratio: 1.5,
+            link_color: None,
+            link_underline: true,
+        }
+    }
+
+    /// ページの横幅をグリッド数で返す
+    pub fn grid_cols(&self) -> f32 {
+        self.page_width_pt.0 / self.base_font_size.0
+    }
+
+    /// ページの高さをグリッド数で返す
+    pub fn grid_rows(&self) -> f32 {
+        self.page_height_pt.0 / self.base_font_size.0
+    }
+}
+
+// --- === 中間表現とレイアウト === ---
+
+// 1. 中間表現: スタイル付きのテキスト断片
+#[derive(Debug, Clone)]
+pub struct TextSpan {
+    pub text: String,
+    pub style: FontStyle,
+    pub size_ratio: f32,
+    pub color: SlideColor,
+    pub link: Option<Link>, // 文字の範囲をクリックしたときの飛び先
+}
+
+/// リンクの飛び先
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTarget {
+    Uri(String),
+    Slide(usize), // 1始まりのスライド番号
+}
+
+impl LinkTarget {
+    /// "#3" はデッキの3枚目のスライド、それ以外はURIとして扱う
+    pub fn parse(text: &str) -> Option<LinkTarget> {
+        match text.strip_prefix('#') {
+            Some(number) => number.parse::<usize>().ok().filter(|n| *n > 0).map(LinkTarget::Slide),
+            None if text.is_empty() => None,
+            None => Some(LinkTarget::Uri(text.to_string())),
+        }
+    }
+}
+
+/// Spanに付けるリンク。文字の範囲がそのままクリックできる範囲になる
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub target: LinkTarget,
+    pub underline: bool,
+}
+
+// 2. 中間表現: テキスト断片か、改行のような制御命令かを表す
+#[derive(Debug, Clone)]
+pub enum Content {
+    Span(TextSpan),
+    Newline,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum VAlign {
+    Top,
+    Middle,
+    Bottom, // ベースライン揃え
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum HAlign {
+    Left,
+    Center,
+    Right,
+    Justify, // 両端揃え (段落の最終行は左揃え)
+}
+
+/// 【低レベル関数】単一のTextSpanを、指定された絶対グリッド座標に描画する
+pub(crate) fn add_single_span(
+    ops: &mut Vec<DrawOp>,
+    config: &DrawConfig,
+    span: &TextSpan,
+    col: f32,
+    row: f32,
+    letter_spacing: f32, // 文字ごとに追加する間隔 (グリッド単位)
+) {
+    let final_font_size = config.base_font_size.0 * span.size_ratio;
+
+    let base_unit_pt = config.base_font_size.0;
+    let x_pt = col * base_unit_pt;
+    let y_pt_from_top = row * base_unit_pt;
+
+    let y_from_bottom_pt = config.page_height_pt.0 - y_pt_from_top;
+    let baseline_y = y_from_bottom_pt - final_font_size;
+
+    ops.push(DrawOp::Text {
+        x: x_pt,
+        baseline: baseline_y,
+        style: span.style,
+        size: final_font_size,
+        color: span.color,
+        letter_spacing: letter_spacing * base_unit_pt,
+        text: span.text.clone(),
+    });
+}
+
+/// Spanに付いたリンクの範囲 (と下線) の描画命令。`width` はSpanの送り幅 (グリッド単位)
+fn link_ops(config: &DrawConfig, span: &TextSpan, link: &Link, col: f32, row: f32, width: f32) -> Vec<DrawOp> {
+    let grid = config.base_font_size.0;
+    let font_size = grid * span.size_ratio;
+    let x = col * grid;
+    let top = config.page_height_pt.0 - row * grid;
+    let mut ops = Vec::new();
+    if link.underline {
+        // ベースラインから文字サイズの1割下に、文字サイズの5%の太さで引く
+        let underline = Frame { x, y_top: top - font_size * 1.1, width: width * grid, height: font_size * 0.05 };
+        ops.push(fill_rect(underline, span.color));
+    }
+    // リンクの範囲は文字の上端からディセンダーの下まで
+    let frame = Frame { x, y_top: top, width: width * grid, height: font_size * 1.2 };
+    ops.push(DrawOp::Link { frame, target: link.target.clone() });
+    ops
+}
+
+/// Spanの送り幅をグリッド単位で返す
+pub(crate) fn span_width(fonts: &HashMap<FontStyle, LoadedFont>, span: &TextSpan) -> Result<f32, SlideError> {
+    let font = font_for(fonts, span.style)?;
+    Ok(font.text_width_em(&span.text) * span.size_ratio)
+}
+
+/// テキストブロックの配置設定 (単位はすべてグリッド)
+#[derive(Debug, Clone, Copy)]
+pub struct BlockLayout {
+    pub start_col: f32,
+    pub start_row: f32,
+    pub line_spacing_ratio: f32,
+    pub align: VAlign,
+    pub h_align: HAlign,
+    pub width: Option<f32>, // ブロックの幅。折り返しと水平揃えの基準。Noneなら明示的なNewlineでのみ改行する
+}
+
+impl BlockLayout {
+    /// 指定したグリッド座標に置く、左揃え・折り返しなしの配置を作る
+    pub fn at(start_col: f32, start_row: f32) -> Self {
+        BlockLayout { start_col, start_row, line_spacing_ratio: 1.2, align: VAlign::Top, h_align: HAlign::Left, width: None }
+    }
+
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn with_line_spacing(mut self, line_spacing_ratio: f32) -> Self {
+        self.line_spacing_ratio = line_spacing_ratio;
+        self
+    }
+
+    pub fn with_align(mut self, align: VAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_h_align(mut self, h_align: HAlign) -> Self {
+        self.h_align = h_align;
+        self
+    }
+}
+
+/// 1行分のSpanを、折り返し幅に収まる複数の行に分割する。
+/// 改行位置は linebreak::can_break_between に従い、Spanのスタイルは分割後も保たれる。
+fn wrap_line(fonts: &HashMap<FontStyle, LoadedFont>, spans: &[&TextSpan], wrap_width: f32) -> Result<Vec<Vec<TextSpan>>, SlideError> {
+    // (文字, 元のSpanの番号, 幅) の列に展開して、文字単位で詰めていく
+    let mut chars: Vec<(char, usize, f32)> = Vec::new();
+    for (span_index, span) in spans.iter().enumerate() {
+        let font = font_for(fonts, span.style)?;
+        let mut buf = [0u8; 4];
+        for c in span.text.chars() {
+            chars.push((c, span_index, font.text_width_em(c.encode_utf8(&mut buf)) * span.size_ratio));
+        }
+    }
+
+    let mut lines: Vec<Vec<TextSpan>> = Vec::new();
+    let mut line_start = 0;
+    while line_start < chars.len() {
+        let mut width = 0.0;
+        let mut last_break: Option<usize> = None; // この位置の文字の直前で改行できる
+        let mut line_end = chars.len();
+
+        for i in line_start..chars.len() {
+            let (c, _, w) = chars[i];
+            if i > line_start && linebreak::can_break_between(chars[i - 1].0, c) {
+                last_break = Some(i);
+            }
+            // 行末の空白は幅に数えない
+            if width + w > wrap_width && !c.is_whitespace() && i > line_start {
+                // 改行できる位置が無ければ、禁則を破ってでもここで折り返す
+                line_end = last_break.unwrap_or(i);
+                break;
+            }
+            width += w;
+        }
+
+        // 文字の範囲を、元のSpanごとのまとまりに戻す
+        let mut line: Vec<TextSpan> = Vec::new();
+        let mut segment_start = line_start;
+        for i in line_start..=line_end {
+            if i == line_end || chars[i].1 != chars[segment_start].1 {
+                let span = spans[chars[segment_start].1];
+                let text: String = chars[segment_start..i].iter().map(|(c, _, _)| *c).collect();
+                line.push(TextSpan { text, ..span.clone() });
+                segment_start = i;
+            }
+        }
+        // 行末の空白は描画しない (空白だけのSpanで終わるときは、その前のSpanの行末の空白も取り除く)
+        while let Some(last) = line.last_mut() {
+            last.text.truncate(last.text.trim_end().len());
+            if !last.text.is_empty() {
+                break;
+            }
+            line.pop();
+        }
+        lines.push(line);
+        line_start = line_end;
+    }
+
+    if lines.is_empty() {
+        lines.push(Vec::new()); // 空行も1行として扱う
+    }
+    Ok(lines)
+}
+
+/// 【高レベル関数】Contentのリストを受け取り、ブロックとしてレイアウトして描画する。
+/// 戻り値は最後の行の次の行の位置 (グリッド単位) で、続けて別のブロックを置くときに使う
+pub(crate) fn draw_text_block(
+    ops: &mut Vec<DrawOp>,
+    fonts: &HashMap<FontStyle, LoadedFont>,
+    config: &DrawConfig,
+    contents: &[Content],
+    layout: &BlockLayout,
+) -> Result<f32, SlideError> {
+    let mut current_row = layout.start_row;
+    let mut current_content_index = 0;
+
+    // contentsがなくなるまで、一行ずつループ処理
+    while current_content_index < contents.len() {
+        // --- 1. 測定パス ---
+        // 次のNewlineまでに含まれるSpanを収集し、必要なら折り返し幅で複数行に分割する
+        let mut spans_in_line: Vec<&TextSpan> = Vec::new();
+        let mut line_end_index = current_content_index;
+
+        for (i, content) in contents.iter().enumerate().skip(current_content_index) {
+            match content {
+                Content::Span(span) => {
+                    spans_in_line.push(span);
+                    line_end_index = i + 1;
+                },
+                Content::Newline => {
+                    line_end_index = i + 1;
+                    break; // 改行が見つかったらこの行はここまで
+                },
+            }
+        }
+
+        let visual_lines: Vec<Vec<TextSpan>> = match layout.width {
+            Some(wrap_width) => wrap_line(fonts, &spans_in_line, wrap_width)?,
+            None => vec![spans_in_line.into_iter().cloned().collect()],
+        };
+
+        for (line_index, line) in visual_lines.iter().enumerate() {
+            // 行内で最大のフォントサイズ比率を見つける
+            let max_font_size_ratio = line.iter().map(|span| span.size_ratio).fold(1.0, f32::max);
+
+            // 行の幅を測り、ブロック幅との差から水平方向の配置を決める
+            let line_width: f32 = line.iter().map(|span| span_width(fonts, span)).sum::<Result<f32, _>>()?;
+            let free_width = layout.width.map_or(0.0, |width| (width - line_width).max(0.0));
+            let is_last_line = line_index + 1 == visual_lines.len();
+            let (x_offset, letter_spacing) = match layout.h_align {
+                HAlign::Left => (0.0, 0.0),
+                HAlign::Center => (free_width / 2.0, 0.0),
+                HAlign::Right => (free_width, 0.0),
+                // 両端揃え: 余った幅を文字間に均等に配る
+                HAlign::Justify => {
+                    let gaps = line.iter().map(|span| span.text.chars().count()).sum::<usize>().saturating_sub(1);
+                    if is_last_line || gaps == 0 { (0.0, 0.0) } else { (0.0, free_width / gaps as f32) }
+                }
+            };
+
+            // --- 2. 描画パス ---
+            // 収集したSpanを、配置モードに基づいて描画していく
+            let mut current_col = layout.start_col + x_offset;
+            for span in line {
+                // 配置モードに応じて、Y座標のオフセットを計算
+                let y_offset = match layout.align {
+                    // Top揃え: オフセットなし。spanの上端は行の上端に揃う。
+                    VAlign::Top => 0.0,
+                    // Middle揃え: 行の高さの中心と、spanの高さの中心を合わせる
+                    VAlign::Middle => (max_font_size_ratio - span.size_ratio) / 2.0,
+                    // Bottom(ベースライン)揃え: spanの上端を下にずらし、ベースラインを合わせる
+                    VAlign::Bottom => max_font_size_ratio - span.size_ratio,
+                };
+
+                // 調整後の行座標(row)で低レベル描画関数を呼び出す
+                add_single_span(ops, config, span, current_col, current_row + y_offset, letter_spacing);
+
+                // 仮想カーソルを右に進める (1グリッド = base_font_size なので、em幅 × size_ratio がそのままグリッド数になる)
+                let advance = span_width(fonts, span)? + span.text.chars().count() as f32 * letter_spacing;
+                if let Some(link) = &span.link {
+                    ops.extend(link_ops(config, span, link, current_col, current_row + y_offset, advance));
+                }
+                current_col += advance;
+            }
+
+            // --- 仮想カーソルの更新 ---
+            // 次の行の開始位置に移動
+            current_row += max_font_size_ratio * layout.line_spacing_ratio;
+        }
+        // 処理済みのコンテンツをスキップ
+        current_content_index = line_end_index;
+    }
+    Ok(current_row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::test_fonts;
+
+    fn span(text: &str, style: FontStyle) -> TextSpan {
+        TextSpan { text: text.to_string(), style, size_ratio: 1.0, color: SlideColor::Named(NamedColor::Black), link: None }
+    }
+
+    /// 折り返した各行を、Spanの文字列の列にする
+    fn wrap(spans: &[TextSpan], width: f32) -> Vec<Vec<String>> {
+        let spans: Vec<&TextSpan> = spans.iter().collect();
+        let lines = wrap_line(&test_fonts(), &spans, width).unwrap();
+        lines.into_iter().map(|line| line.into_iter().map(|span| span.text).collect()).collect()
+    }
+
+    fn wrap_text(text: &str, width: f32) -> Vec<String> {
+        wrap(&[span(text, FontStyle::Regular)], width).into_iter().map(|line| line.concat()).collect()
+    }
+
+    #[test]
+    fn test_font_widths() {
+        let fonts = test_fonts();
+        assert_eq!(span_width(&fonts, &span("ab", FontStyle::Regular)).unwrap(), 1.0);
+        assert_eq!(span_width(&fonts, &span("日本", FontStyle::Regular)).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn cjk_breaks_between_any_characters() {
+        assert_eq!(wrap_text("あいうえおか", 3.0), ["あいう", "えおか"]);
+    }
+
+    #[test]
+    fn kinsoku_keeps_closing_marks_off_the_line_start() {
+        // "、" と "。" は行頭に来ないよう、直前の文字ごと次の行に送る
+        assert_eq!(wrap_text("これは、テストです。", 3.0), ["これ", "は、テ", "ストで", "す。"]);
+        assert_eq!(wrap_text("あいう）えお", 3.0), ["あい", "う）え", "お"]);
+    }
+
+    #[test]
+    fn kinsoku_keeps_opening_marks_off_the_line_end() {
+        assert_eq!(wrap_text("あい「う」", 3.0), ["あい", "「う」"]);
+    }
+
+    #[test]
+    fn latin_breaks_at_word_boundaries() {
+        assert_eq!(wrap_text("hello world foo", 6.0), ["hello world", "foo"]);
+        assert_eq!(wrap_text("hello world foo", 4.0), ["hello", "world", "foo"]);
+        // 単語の途中では切らず、和文との境目では切れる
+        assert_eq!(wrap_text("日本語text", 4.0), ["日本語", "text"]);
+    }
+
+    #[test]
+    fn unbreakable_runs_are_cut_at_the_width() {
+        assert_eq!(wrap_text("abcdefgh", 2.0), ["abcd", "efgh"]);
+        assert_eq!(wrap_text("「「「「", 2.0), ["「「", "「「"]);
+    }
+
+    #[test]
+    fn empty_line_is_kept() {
+        assert_eq!(wrap(&[], 4.0), [Vec::<String>::new()]);
+    }
+
+    #[test]
+    fn multi_span_lines_keep_their_styles() {
+        let spans = [span("hello ", FontStyle::Regular), span("world", FontStyle::Bold), span(" foo", FontStyle::Regular)];
+        let lines = wrap_line(&test_fonts(), &spans.iter().collect::<Vec<_>>(), 6.0).unwrap();
+        let texts: Vec<Vec<(&str, FontStyle)>> =
+            lines.iter().map(|line| line.iter().map(|span| (span.text.as_str(), span.style)).collect()).collect();
+        assert_eq!(texts, [vec![("hello ", FontStyle::Regular), ("world", FontStyle::Bold)], vec![("foo", FontStyle::Regular)]]);
+    }
+
+    #[test]
+    fn a_word_split_across_spans_is_not_broken() {
+        let spans = [span("ab", FontStyle::Regular), span("cd", FontStyle::Bold), span(" ef", FontStyle::Regular)];
+        assert_eq!(wrap(&spans, 2.5), [vec!["ab", "cd"], vec!["ef"]]);
+    }
+
+    #[test]
+    fn trailing_whitespace_is_trimmed_across_spans() {
+        let spans = [span("ab ", FontStyle::Regular), span("  ", FontStyle::Bold), span("cdef", FontStyle::Regular)];
+        assert_eq!(wrap(&spans, 2.0), [vec!["ab"], vec!["cdef"]]);
+    }
+}
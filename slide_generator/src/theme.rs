@@ -0,0 +1,333 @@
+// --- テーマファイル (TOML) ---
+//
+// デッキ全体の見た目 (フォント、色、文字サイズ、行送り、背景、マスター) をまとめて定義する。
+// 書式は themes/default.toml を参照。フォントや画像のパスはテーマファイルからの相対パスで書く。
+
+use crate::deck::PLACEHOLDERS;
+use crate::error::SlideError;
+use crate::markup;
+use crate::{Background, BackgroundImage, BlockLayout, DrawConfig, HAlign, ImageFit, ImageLayout, Master, NamedColor, SlideColor};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 読み込み済みのテーマ。色はすべて解決済み
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub font_regular: PathBuf,
+    pub font_bold: PathBuf,
+    pub font_italic: PathBuf,
+    pub font_mono: PathBuf,
+    pub palette: HashMap<String, SlideColor>,
+    pub text_color: SlideColor,
+    pub background: Background,
+    pub title_size_ratio: f32,
+    pub subtitle_size_ratio: f32,
+    pub body_size_ratio: f32,
+    pub line_spacing_ratio: f32,
+    pub link_color: Option<SlideColor>,
+    pub link_underline: bool,
+    pub masters: HashMap<String, Master>, // "default" は指定の無いすべてのスライドに使う
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            font_regular: PathBuf::from("fonts/RictyDiminished-Regular.ttf"),
+            font_bold: PathBuf::from("fonts/RictyDiminished-Bold.ttf"),
+            // 同梱のフォントには斜体が無いので、通常のフォントで代用する
+            font_italic: PathBuf::from("fonts/RictyDiminished-Regular.ttf"),
+            font_mono: PathBuf::from("fonts/RictyDiminished-Regular.ttf"),
+            palette: HashMap::new(),
+            text_color: SlideColor::Named(NamedColor::Black),
+            background: Background::default(),
+            title_size_ratio: 2.0,
+            subtitle_size_ratio: 1.5,
+            body_size_ratio: 1.0,
+            line_spacing_ratio: 1.5,
+            link_color: None,
+            link_underline: true,
+            masters: HashMap::new(),
+        }
+    }
+}
+
+// --- TOMLの構造そのまま (省略された項目は既定値) ---
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    text_color: Option<String>,
+    background: Option<String>,
+    background_image: Option<PathBuf>,
+    background_fit: Option<String>,
+    line_spacing: Option<f32>,
+    fonts: FontsTable,
+    sizes: SizesTable,
+    colors: HashMap<String, String>,
+    links: LinksTable,
+    masters: HashMap<String, MasterTable>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FontsTable {
+    regular: Option<PathBuf>,
+    bold: Option<PathBuf>,
+    italic: Option<PathBuf>,
+    mono: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct SizesTable {
+    title: Option<f32>,
+    subtitle: Option<f32>,
+    body: Option<f32>,
+}
+
+/// [links] : マークアップのリンクの見た目
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct LinksTable {
+    color: Option<String>,
+    underline: Option<bool>,
+}
+
+/// [masters.名前] : 繰り返し描くテキストと画像
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct MasterTable {
+    text: Vec<MasterText>,
+    image: Vec<MasterImage>,
+}
+
+/// [[masters.名前.text]] : マークアップと "{page}" などが使えるテキスト
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MasterText {
+    text: String,
+    col: f32,
+    row: f32,
+    width: Option<f32>,
+    size: Option<f32>,
+    color: Option<String>,
+    align: Option<String>,
+}
+
+/// [[masters.名前.image]] : ロゴなどの画像 (テーマファイルからの相対パス)
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MasterImage {
+    src: PathBuf,
+    col: f32,
+    row: f32,
+    width: f32,
+    height: f32,
+    fit: Option<String>,
+}
+
+impl Theme {
+    /// テーマファイルを読み込み、色名やフォントのパスを解決する
+    pub fn load(path: &Path) -> Result<Theme, SlideError> {
+        let text = fs::read_to_string(path).map_err(|source| SlideError::ThemeRead { path: path.to_path_buf(), source })?;
+        let invalid = |message: String| SlideError::ThemeInvalid { path: path.to_path_buf(), message };
+        let file: ThemeFile = toml::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+
+        let mut theme = Theme::default();
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        if let Some(regular) = file.fonts.regular {
+            theme.font_regular = base_dir.join(regular);
+        }
+        if let Some(bold) = file.fonts.bold {
+            theme.font_bold = base_dir.join(bold);
+        }
+        if let Some(italic) = file.fonts.italic {
+            theme.font_italic = base_dir.join(italic);
+        }
+        if let Some(mono) = file.fonts.mono {
+            theme.font_mono = base_dir.join(mono);
+        }
+
+        // [colors] は組み込みの色名か "#rrggbb" で定義する (名前は大文字小文字を区別しない)
+        let builtin = HashMap::new();
+        for (name, value) in &file.colors {
+            let color = SlideColor::parse(value, &builtin).ok_or_else(|| invalid(format!("colors.{}: unknown color '{}'", name, value)))?;
+            theme.palette.insert(name.to_ascii_lowercase(), color);
+        }
+        // 文字色と背景色では [colors] の名前も使える
+        let resolve = |key: &str, value: &str| {
+            SlideColor::parse(value, &theme.palette).ok_or_else(|| invalid(format!("{}: unknown color '{}'", key, value)))
+        };
+        if let Some(value) = &file.text_color {
+            theme.text_color = resolve("text_color", value)?;
+        }
+        if let Some(value) = &file.background {
+            theme.background.color = Some(resolve("background", value)?);
+        }
+        if let Some(value) = &file.links.color {
+            theme.link_color = Some(resolve("links.color", value)?);
+        }
+        if let Some(underline) = file.links.underline {
+            theme.link_underline = underline;
+        }
+        // 背景画像のパスもテーマファイルからの相対パス
+        let fit = match &file.background_fit {
+            Some(name) => ImageFit::from_name(name).ok_or_else(|| invalid(format!("background_fit: unknown fit '{}' (expected cover, contain or stretch)", name)))?,
+            None => ImageFit::Cover,
+        };
+        if let Some(image) = file.background_image {
+            theme.background.image = Some(BackgroundImage { path: base_dir.join(image), fit });
+        }
+
+        let positive = |key: &str, value: Option<f32>, default: f32| match value {
+            Some(v) if v.is_finite() && v > 0.0 => Ok(v),
+            Some(v) => Err(invalid(format!("{} must be positive, got {}", key, v))),
+            None => Ok(default),
+        };
+        theme.title_size_ratio = positive("sizes.title", file.sizes.title, theme.title_size_ratio)?;
+        theme.subtitle_size_ratio = positive("sizes.subtitle", file.sizes.subtitle, theme.subtitle_size_ratio)?;
+        theme.body_size_ratio = positive("sizes.body", file.sizes.body, theme.body_size_ratio)?;
+        theme.line_spacing_ratio = positive("line_spacing", file.line_spacing, theme.line_spacing_ratio)?;
+
+        // マスターのテキストは、ここまでに決まった文字色と色名でマークアップを解釈する
+        let mut config = DrawConfig::from_grid(1.0, 1.0, 1.0);
+        theme.apply_to(&mut config);
+        for (name, table) in file.masters {
+            let mut master = Master::default();
+            for (index, text) in table.text.iter().enumerate() {
+                let key = format!("masters.{}.text[{}]", name, index);
+                let mut text_config = config.clone();
+                if let Some(value) = &text.color {
+                    text_config.default_color = SlideColor::parse(value, &config.palette)
+                        .ok_or_else(|| invalid(format!("{}.color: unknown color '{}'", key, value)))?;
+                }
+                let size_ratio = positive(&format!("{}.size", key), text.size, config.body_size_ratio)?;
+                // "{page}" などはマークアップのタグではないので、そのままの文字として残す
+                let escaped = PLACEHOLDERS.iter().fold(text.text.clone(), |escaped, placeholder| {
+                    escaped.replace(placeholder, &format!("\\{}", placeholder))
+                });
+                let contents = markup::parse_inline(&escaped, &text_config, config.default_font_style, size_ratio)
+                    .map_err(|e| invalid(format!("{}.text: {}", key, e)))?;
+
+                let mut layout = BlockLayout::at(text.col, text.row);
+                if let Some(width) = text.width {
+                    if width <= 0.0 {
+                        return Err(invalid(format!("{}.width must be positive, got {}", key, width)));
+                    }
+                    layout = layout.with_width(width);
+                }
+                let h_align = match text.align.as_deref() {
+                    None | Some("left") => HAlign::Left,
+                    Some("center") => HAlign::Center,
+                    Some("right") => HAlign::Right,
+                    Some(other) => return Err(invalid(format!("{}.align: unknown align '{}' (expected left, center or right)", key, other))),
+                };
+                if !matches!(h_align, HAlign::Left) && text.width.is_none() {
+                    return Err(invalid(format!("{}.align needs a width", key)));
+                }
+                master.add_text_block(contents, layout.with_h_align(h_align));
+            }
+            for (index, image) in table.image.iter().enumerate() {
+                let key = format!("masters.{}.image[{}]", name, index);
+                // スライドの @image と同じく、位置は0以上、大きさは正の有限の値に限る
+                for (field, value) in [("col", image.col), ("row", image.row)] {
+                    if !(value.is_finite() && value >= 0.0) {
+                        return Err(invalid(format!("{}.{} must not be negative, got {}", key, field, value)));
+                    }
+                }
+                for (field, value) in [("width", image.width), ("height", image.height)] {
+                    if !(value.is_finite() && value > 0.0) {
+                        return Err(invalid(format!("{}.{} must be positive, got {}", key, field, value)));
+                    }
+                }
+                let mut layout = ImageLayout::at(image.col, image.row, image.width, image.height);
+                if let Some(fit) = &image.fit {
+                    let fit = ImageFit::from_name(fit)
+                        .ok_or_else(|| invalid(format!("{}.fit: unknown fit '{}' (expected contain, cover or stretch)", key, fit)))?;
+                    layout = layout.with_fit(fit);
+                }
+                master.add_image(base_dir.join(&image.src), layout);
+            }
+            theme.masters.insert(name, master);
+        }
+        Ok(theme)
+    }
+
+    /// テーマの色・サイズを描画設定に反映する (フォントは Deck::load_font で別途読み込む)
+    pub fn apply_to(&self, config: &mut DrawConfig) {
+        config.default_color = self.text_color;
+        config.palette = self.palette.clone();
+        config.background = self.background.clone();
+        config.title_size_ratio = self.title_size_ratio;
+        config.subtitle_size_ratio = self.subtitle_size_ratio;
+        config.body_size_ratio = self.body_size_ratio;
+        config.line_spacing_ratio = self.line_spacing_ratio;
+        config.link_color = self.link_color;
+        config.link_underline = self.link_underline;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `text` を一時ファイルに書いてテーマとして読み込む
+    fn load(name: &str, text: &str) -> Result<Theme, SlideError> {
+        let path = std::env::temp_dir().join(format!("slide_generator_theme_{}_{}.toml", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        let theme = Theme::load(&path);
+        fs::remove_file(&path).unwrap();
+        theme
+    }
+
+    fn message(result: Result<Theme, SlideError>) -> String {
+        match result.expect_err("expected an invalid theme") {
+            SlideError::ThemeInvalid { message, .. } => message,
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn default_theme_loads() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("themes/default.toml");
+        let theme = Theme::load(&path).unwrap();
+        assert_eq!(theme.font_bold, path.parent().unwrap().join("../fonts/RictyDiminished-Bold.ttf"));
+        assert_eq!((theme.title_size_ratio, theme.subtitle_size_ratio, theme.body_size_ratio), (2.0, 1.5, 1.0));
+        assert_eq!(theme.line_spacing_ratio, 1.5);
+        assert!(theme.link_underline);
+        assert!(theme.masters.is_empty());
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(message(load("unknown_top", "text_colour = \"red\"\n")).contains("unknown field `text_colour`"));
+        assert!(message(load("unknown_sizes", "[sizes]\nheading = 2.0\n")).contains("unknown field `heading`"));
+    }
+
+    #[test]
+    fn sizes_must_be_positive_and_finite() {
+        assert_eq!(message(load("size_zero", "[sizes]\ntitle = 0.0\n")), "sizes.title must be positive, got 0");
+        assert_eq!(message(load("size_inf", "[sizes]\nbody = inf\n")), "sizes.body must be positive, got inf");
+        assert_eq!(message(load("spacing", "line_spacing = -1.0\n")), "line_spacing must be positive, got -1");
+        let theme = load("size_ok", "[sizes]\nsubtitle = 1.25\n").unwrap();
+        assert_eq!(theme.subtitle_size_ratio, 1.25);
+    }
+
+    #[test]
+    fn master_images_need_a_positive_size() {
+        let image = |fields: &str| format!("[[masters.logo.image]]\nsrc = \"logo.png\"\n{}\n", fields);
+        let theme = load("image_ok", &image("col = 0\nrow = 1\nwidth = 2\nheight = 3")).unwrap();
+        assert!(theme.masters.contains_key("logo"));
+        for (name, fields, expected) in [
+            ("image_width", "col = 0\nrow = 0\nwidth = 0\nheight = 1", "masters.logo.image[0].width must be positive, got 0"),
+            ("image_height", "col = 0\nrow = 0\nwidth = 1\nheight = -2", "masters.logo.image[0].height must be positive, got -2"),
+            ("image_inf", "col = 0\nrow = 0\nwidth = inf\nheight = 1", "masters.logo.image[0].width must be positive, got inf"),
+            ("image_col", "col = -1\nrow = 0\nwidth = 1\nheight = 1", "masters.logo.image[0].col must not be negative, got -1"),
+            ("image_row", "col = 0\nrow = nan\nwidth = 1\nheight = 1", "masters.logo.image[0].row must not be negative, got NaN"),
+        ] {
+            assert_eq!(message(load(name, &image(fields))), expected);
+        }
+    }
+}